@@ -6,11 +6,17 @@ use std::path::{Path, PathBuf};
 
 use sqlparser::{
     ast::Statement,
-    dialect::Dialect,
+    dialect::{Dialect, GenericDialect, dialect_from_str},
+    keywords::Keyword,
     parser::{Parser, ParserError},
+    tokenizer::{Location as TokenLocation, Token, TokenWithSpan, Tokenizer},
 };
 
-use crate::source::SqlSource;
+use crate::{
+    comments::{Location, Span},
+    error::DocError,
+    source::SqlSource,
+};
 
 /// A single SQL file plus all [`Statement`].
 #[derive(Debug)]
@@ -34,8 +40,83 @@ impl ParsedSqlFile {
     where
         D: Dialect + Default,
     {
-        let statements = Parser::parse_sql(&D::default(), file.content())?;
-        Ok(Self { file, statements })
+        Self::parse_with_dialect(file, &D::default())
+    }
+
+    /// Parses a [`SqlSource`] with a dialect chosen at runtime.
+    ///
+    /// # Parameters
+    /// - `file`: the [`SqlSource`] to parse
+    /// - `dialect`: the [`Dialect`] to parse with, e.g. from [`dialect_from_name`]
+    ///
+    /// # Errors
+    /// - Returns [`ParserError`] if parsing fails
+    pub fn parse_with_dialect(file: SqlSource, dialect: &dyn Dialect) -> Result<Self, ParserError> {
+        Self::try_parse(file, dialect).map_err(|(error, _)| error)
+    }
+
+    /// Parses `file` like [`ParsedSqlFile::parse_with_dialect`], handing the
+    /// file back with the error so the caller can point into it
+    pub(crate) fn try_parse(
+        file: SqlSource,
+        dialect: &dyn Dialect,
+    ) -> Result<Self, (ParserError, SqlSource)> {
+        match Parser::parse_sql(dialect, file.content()) {
+            Ok(statements) => Ok(Self { file, statements }),
+            Err(error) => Err((error, file)),
+        }
+    }
+
+    /// Parses a [`SqlSource`] without knowing its dialect up front.
+    ///
+    /// Every dialect in [`DETECTABLE_DIALECTS`] is tried; among those that
+    /// parse the file, the one matching the most dialect-specific syntax
+    /// (backtick identifiers, `$$` strings, `AUTO_INCREMENT`, `OPTIONS(...)`,
+    /// `::` casts) is chosen, with ties going to the earlier entry in the list.
+    ///
+    /// # Parameters
+    /// - `file`: the [`SqlSource`] to parse
+    ///
+    /// # Errors
+    /// - Returns the error from parsing with [`GenericDialect`] if no candidate
+    ///   dialect can parse the file
+    ///
+    /// # Examples
+    /// ```
+    /// use sql_docs::{ast::ParsedSqlFile, source::SqlSource};
+    ///
+    /// let sql = "CREATE TABLE `users` (id INT AUTO_INCREMENT);";
+    /// let file = SqlSource::from_str(sql.to_owned(), None);
+    /// let (_, dialect) = ParsedSqlFile::parse_detecting_dialect(file).unwrap();
+    /// assert_eq!(dialect, "mysql");
+    /// ```
+    pub fn parse_detecting_dialect(file: SqlSource) -> Result<(Self, &'static str), ParserError> {
+        Self::try_parse_detecting_dialect(file).map_err(|(error, _)| error)
+    }
+
+    /// Parses `file` like [`ParsedSqlFile::parse_detecting_dialect`], handing
+    /// the file back with the error so the caller can point into it
+    pub(crate) fn try_parse_detecting_dialect(
+        file: SqlSource,
+    ) -> Result<(Self, &'static str), (ParserError, SqlSource)> {
+        let hints = syntax_hints(file.content());
+        let mut best: Option<(usize, &'static str, Vec<Statement>)> = None;
+        for name in DETECTABLE_DIALECTS {
+            let Some(dialect) = dialect_from_str(name) else {
+                continue;
+            };
+            let Ok(statements) = Parser::parse_sql(dialect.as_ref(), file.content()) else {
+                continue;
+            };
+            let score = hints.iter().filter(|hint| hint.dialects().contains(name)).count();
+            if best.as_ref().is_none_or(|(best_score, ..)| score > *best_score) {
+                best = Some((score, name, statements));
+            }
+        }
+        let Some((_, name, statements)) = best else {
+            return Self::try_parse(file, &GenericDialect {}).map(|file| (file, "generic"));
+        };
+        Ok((Self { file, statements }, name))
     }
 
     /// Getter method for returning the [`SqlSource`]
@@ -87,8 +168,25 @@ impl ParsedSqlFileSet {
     where
         D: Dialect + Default,
     {
-        let files =
-            set.into_iter().map(ParsedSqlFile::parse::<D>).collect::<Result<Vec<_>, _>>()?;
+        Self::parse_all_with_dialect(set, &D::default())
+    }
+
+    /// Parses every member of a set of [`SqlSource`] with a dialect chosen at runtime.
+    ///
+    /// # Parameters
+    /// - `set` the set of [`SqlSource`]
+    /// - `dialect`: the [`Dialect`] to parse with, e.g. from [`dialect_from_name`]
+    ///
+    /// # Errors
+    /// - [`ParserError`] is returned for any errors parsing
+    pub fn parse_all_with_dialect(
+        set: Vec<SqlSource>,
+        dialect: &dyn Dialect,
+    ) -> Result<Self, ParserError> {
+        let files = set
+            .into_iter()
+            .map(|file| ParsedSqlFile::parse_with_dialect(file, dialect))
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Self { files })
     }
@@ -100,6 +198,166 @@ impl ParsedSqlFileSet {
     }
 }
 
+/// Finds the [`Span`] where `sql` fails to parse with `dialect` from the
+/// tokens themselves: the location the tokenizer stopped at, or the last
+/// token the parser accepted through the one it stopped at. Returns `None`
+/// if `sql` parses.
+pub(crate) fn parse_error_span(sql: &str, dialect: &dyn Dialect) -> Option<Span> {
+    let tokens = match Tokenizer::new(dialect, sql).tokenize_with_location() {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            let start = location(error.location)?;
+            return Some(Span::new(start, Location::new(start.line(), start.column() + 1)));
+        }
+    };
+    let mut parser = Parser::new(dialect).with_tokens_with_locations(tokens.clone());
+    parser.parse_statements().err()?;
+    let (accepted, rest) = tokens.split_at(parser.index().min(tokens.len()));
+    let previous = accepted.iter().rev().find(|token| !is_whitespace(token));
+    let current = rest.iter().find(|token| !is_whitespace(token));
+    let start = location(previous.or(current)?.span.start)?;
+    let end = location(current.or(previous)?.span.end)?;
+    Some(Span::new(start, end))
+}
+
+/// Converts a `sqlparser` location, which is `0:0` when unknown
+fn location(location: TokenLocation) -> Option<Location> {
+    (location.line > 0).then(|| Location::new(location.line, location.column))
+}
+
+const fn is_whitespace(token: &TokenWithSpan) -> bool {
+    matches!(token.token, Token::Whitespace(_))
+}
+
+/// Looks up a [`Dialect`] by name, accepting the same names as `sqlparser`'s
+/// [`dialect_from_str`] (`"generic"`, `"postgres"`, `"bigquery"`, ...), case-insensitively.
+///
+/// # Parameters
+/// - `name`: the dialect name, e.g. as read from a configuration file
+///
+/// # Errors
+/// - Returns [`DocError::UnknownDialect`] if no dialect has that name
+pub fn dialect_from_name(name: &str) -> Result<Box<dyn Dialect>, DocError> {
+    dialect_from_str(name.trim()).ok_or_else(|| DocError::UnknownDialect { name: name.to_owned() })
+}
+
+/// The dialects tried by [`ParsedSqlFile::parse_detecting_dialect`], as names
+/// accepted by [`dialect_from_name`], in order of preference when scores tie
+pub const DETECTABLE_DIALECTS: &[&str] = &[
+    "generic",
+    "mysql",
+    "postgresql",
+    "bigquery",
+    "sqlite",
+    "snowflake",
+    "redshift",
+    "duckdb",
+    "clickhouse",
+    "databricks",
+    "hive",
+    "mssql",
+    "oracle",
+    "ansi",
+];
+
+/// Syntax that only some dialects accept, used to rank the dialects that can
+/// parse a file
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SyntaxHint {
+    Backtick,
+    DollarQuote,
+    AutoIncrement,
+    Options,
+    DoubleColon,
+}
+
+impl SyntaxHint {
+    /// The dialects that use this syntax
+    const fn dialects(self) -> &'static [&'static str] {
+        match self {
+            Self::Backtick => &["mysql", "bigquery", "sqlite", "clickhouse", "databricks", "hive"],
+            Self::DollarQuote | Self::DoubleColon => {
+                &["postgresql", "redshift", "duckdb", "snowflake"]
+            }
+            Self::AutoIncrement => &["mysql"],
+            Self::Options => &["bigquery"],
+        }
+    }
+}
+
+/// Collects the distinct [`SyntaxHint`]s in `sql`, ignoring comments and
+/// string contents. Returns no hints if `sql` cannot be tokenized.
+fn syntax_hints(sql: &str) -> Vec<SyntaxHint> {
+    let Ok(tokens) = Tokenizer::new(&GenericDialect {}, sql).tokenize() else {
+        return Vec::new();
+    };
+    let mut tokens =
+        tokens.iter().filter(|token| !matches!(token, Token::Whitespace(_))).peekable();
+    let mut hints = Vec::new();
+    while let Some(token) = tokens.next() {
+        let hint = match token {
+            Token::Word(word) if word.quote_style == Some('`') => SyntaxHint::Backtick,
+            Token::Word(word) if word.keyword == Keyword::AUTO_INCREMENT => {
+                SyntaxHint::AutoIncrement
+            }
+            Token::Word(word)
+                if word.keyword == Keyword::OPTIONS && tokens.peek() == Some(&&Token::LParen) =>
+            {
+                SyntaxHint::Options
+            }
+            Token::DollarQuotedString(_) => SyntaxHint::DollarQuote,
+            Token::DoubleColon => SyntaxHint::DoubleColon,
+            _ => continue,
+        };
+        if !hints.contains(&hint) {
+            hints.push(hint);
+        }
+    }
+    hints
+}
+
+/// The dialect a source was parsed with when dialect detection is enabled,
+/// see [`crate::SqlDocBuilder::detect_dialect`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceDialect {
+    path: Option<PathBuf>,
+    dialect: String,
+    detected: bool,
+}
+
+impl SourceDialect {
+    /// Creates a new [`SourceDialect`]
+    ///
+    /// # Parameters
+    /// - `path`: the path of the source, `None` if it was built from a string
+    /// - `dialect`: the name of the dialect the source was parsed with
+    /// - `detected`: `false` if the dialect came from a header or path override
+    #[must_use]
+    pub const fn new(path: Option<PathBuf>, dialect: String, detected: bool) -> Self {
+        Self { path, dialect, detected }
+    }
+
+    /// Returns the path of the source, `None` if it was built from a string
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the name of the dialect the source was parsed with
+    #[must_use]
+    pub fn dialect(&self) -> &str {
+        &self.dialect
+    }
+
+    /// Returns `true` if the dialect was detected rather than set by a
+    /// `-- dialect:` header or [`crate::SqlDocBuilder::dialect_for`]
+    #[must_use]
+    pub const fn detected(&self) -> bool {
+        self.detected
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{env, fs};
@@ -254,4 +512,50 @@ mod tests {
         let res = ParsedSqlFile::parse::<GenericDialect>(src);
         assert!(res.is_err(), "expected parse to fail for invalid SQL");
     }
+
+    #[test]
+    fn parsed_sql_file_parse_with_dialect_from_name() -> Result<(), Box<dyn std::error::Error>> {
+        let dialect = dialect_from_name("PostgreSQL")?;
+        let src = SqlSource::from_str("CREATE TABLE t (id SERIAL PRIMARY KEY);".to_owned(), None);
+        let parsed = ParsedSqlFile::parse_with_dialect(src, dialect.as_ref())?;
+        assert_eq!(parsed.statements().len(), 1);
+
+        let set = vec![
+            SqlSource::from_str("CREATE TABLE a (id INTEGER);".to_owned(), None),
+            SqlSource::from_str("CREATE TABLE b (id INTEGER);".to_owned(), None),
+        ];
+        let parsed_set = ParsedSqlFileSet::parse_all_with_dialect(set, &GenericDialect {})?;
+        assert_eq!(parsed_set.files().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn dialect_from_name_rejects_unknown_names() {
+        match dialect_from_name("not-a-dialect") {
+            Err(DocError::UnknownDialect { name }) => assert_eq!(name, "not-a-dialect"),
+            Err(e) => panic!("expected UnknownDialect, got: {e:?}"),
+            Ok(d) => panic!("expected error, got {d:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_detecting_dialect_uses_syntax_hints() -> Result<(), Box<dyn std::error::Error>> {
+        let cases = [
+            ("CREATE TABLE users (id INTEGER);", "generic"),
+            ("CREATE TABLE `users` (id INT AUTO_INCREMENT);", "mysql"),
+            ("CREATE TABLE events (id INT64) OPTIONS(description = 'x');", "bigquery"),
+            ("CREATE TABLE t (id INT); SELECT '1'::INT, $$body$$;", "postgresql"),
+            ("-- `quoted` in a comment\nCREATE TABLE t (id INT);", "generic"),
+        ];
+        for (sql, expected) in cases {
+            let file = SqlSource::from_str(sql.to_owned(), None);
+            let (parsed, dialect) = ParsedSqlFile::parse_detecting_dialect(file)?;
+            assert_eq!(dialect, expected, "for {sql:?}");
+            assert_eq!(parsed.statements().len(), sql.matches(';').count());
+        }
+
+        let broken = SqlSource::from_str("CREATE TABLE (;".to_owned(), None);
+        assert!(ParsedSqlFile::parse_detecting_dialect(broken).is_err());
+        Ok(())
+    }
 }
@@ -11,6 +11,7 @@ use crate::ast::ParsedSqlFile;
 
 /// Represents a line/column location within a source file.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
     line: u64,
     column: u64,
@@ -48,6 +49,7 @@ impl Default for Location {
 
 /// Represents a start/end span (inclusive/exclusive as used by this crate) for a comment in a file.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     start: Location,
     end: Location,
@@ -87,6 +89,8 @@ impl Default for Span {
 /// Enum for differentiating comments by single line `--` and
 /// multiline `/* */`
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum CommentKind {
     /// Enum variant for Multiline Comments
     MultiLine,
@@ -96,6 +100,7 @@ pub enum CommentKind {
 
 /// Structure for containing the [`CommentKind`] and the [`Span`] for a comment
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Comment {
     text: String,
     kind: CommentKind,
@@ -131,6 +136,22 @@ impl Comment {
     pub fn text(&self) -> &str {
         &self.text
     }
+
+    /// Returns the dialect name if this is a `-- dialect: <name>` directive
+    #[must_use]
+    pub fn dialect_directive(&self) -> Option<&str> {
+        match self.kind {
+            CommentKind::SingleLine => dialect_directive(&self.text),
+            CommentKind::MultiLine => None,
+        }
+    }
+}
+
+/// Parses the `dialect: <name>` form of a single line comment's text
+pub(crate) fn dialect_directive(text: &str) -> Option<&str> {
+    let (key, name) = text.trim().split_once(':')?;
+    let name = name.trim();
+    (key.trim_end().eq_ignore_ascii_case("dialect") && !name.is_empty()).then_some(name)
 }
 
 /// Enum for returning errors withe Comment parsing
@@ -338,6 +359,17 @@ impl Comments {
         Ok(Self { comments })
     }
 
+    /// Removes the `-- dialect: <name>` directive starting on `line`, which
+    /// chooses how the file is parsed rather than documents anything in it.
+    /// Comments of the same form elsewhere in the file are kept.
+    #[must_use]
+    pub fn without_dialect_directive(mut self, line: u64) -> Self {
+        self.comments.retain(|comment| {
+            comment.span().start().line() != line || comment.dialect_directive().is_none()
+        });
+        self
+    }
+
     /// Getter method for retrieving the Vec of [`Comment`]
     #[must_use]
     pub fn comments(&self) -> &[Comment] {
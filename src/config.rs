@@ -0,0 +1,220 @@
+//! Owned build settings that can be stored, shared across threads and reused.
+
+use std::{io, path::Path};
+
+use crate::{
+    comments::LeadingCommentCapture,
+    encoding::Encoding,
+    error::DocError,
+    files::ScanOptions,
+    sql_doc::{SqlDoc, SqlDocBuilder},
+};
+
+/// The settings of an [`SqlDocBuilder`] as an owned, `'static` value.
+///
+/// Unlike the builder, which borrows its inputs, a config is `Clone`, `Send`
+/// and `Sync`, so it can live in long-lived service state and be applied to
+/// any number of new inputs. Every field is public so a config can be filled
+/// in from a configuration file; [`SqlDocConfig::default`] matches the
+/// defaults of a fresh builder.
+///
+/// # Examples
+/// ```
+/// use sql_docs::{LeadingCommentCapture, SqlDocConfig};
+///
+/// let config = SqlDocConfig {
+///     dialect: "postgres".to_owned(),
+///     leading: LeadingCommentCapture::AllLeading,
+///     flatten: Some(" ".to_owned()),
+///     ..SqlDocConfig::default()
+/// };
+/// let shared = std::sync::Arc::new(config);
+///
+/// let doc = shared.build_str("-- Users\n-- of the app\nCREATE TABLE users (id SERIAL);").unwrap();
+/// assert_eq!(doc.table("users", None).unwrap().doc(), Some("Users of the app"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SqlDocConfig {
+    /// The name of the dialect sources are parsed with, see
+    /// [`crate::ast::dialect_from_name`]
+    pub dialect: String,
+    /// Path globs and the names of the dialects used for files matching them,
+    /// see [`SqlDocBuilder::dialect_for`]
+    pub dialect_overrides: Vec<(String, String)>,
+    /// Whether to detect the dialect of each file, see [`SqlDocBuilder::detect_dialect`]
+    pub detect_dialect: bool,
+    /// Which leading comments are captured for a statement
+    pub leading: LeadingCommentCapture,
+    /// The separator multiline comments are flattened with, or `None` to keep
+    /// their line structure; an empty separator joins the lines directly
+    pub flatten: Option<String>,
+    /// Whether to keep building after a file fails and report every error
+    pub collect_errors: bool,
+    /// The encoding of files, detected from a byte order mark when `None`
+    pub encoding: Option<Encoding>,
+    /// The settings for scanning directories and providers
+    pub scan: ScanOptions,
+    /// Full paths skipped in every source
+    pub deny: Vec<String>,
+}
+
+impl Default for SqlDocConfig {
+    fn default() -> Self {
+        Self {
+            dialect: "generic".to_owned(),
+            dialect_overrides: Vec::new(),
+            detect_dialect: false,
+            leading: LeadingCommentCapture::default(),
+            flatten: None,
+            collect_errors: false,
+            encoding: None,
+            scan: ScanOptions::new(),
+            deny: Vec::new(),
+        }
+    }
+}
+
+impl SqlDocConfig {
+    /// Creates an [`SqlDocBuilder`] without sources, configured with these
+    /// settings; add sources with [`SqlDocBuilder::add_dir`] and its siblings
+    /// and build it with [`SqlDocConfig::build`]
+    #[must_use]
+    pub fn builder(&self) -> SqlDocBuilder<'_> {
+        let mut builder = SqlDoc::builder().scan_options(self.scan.clone());
+        for deny_path in &self.deny {
+            builder = builder.deny(deny_path);
+        }
+        for (pattern, dialect_name) in &self.dialect_overrides {
+            builder = builder.dialect_for(pattern, dialect_name);
+        }
+        if self.detect_dialect {
+            builder = builder.detect_dialect();
+        }
+        if let Some(encoding) = self.encoding {
+            builder = builder.encoding(encoding);
+        }
+        if self.collect_errors {
+            builder = builder.collect_all_errors();
+        }
+        builder = match self.leading {
+            LeadingCommentCapture::SingleNearest => builder.collect_single_nearest(),
+            LeadingCommentCapture::AllLeading => builder.collect_all_leading(),
+            LeadingCommentCapture::AllSingleOneMulti => builder.collect_all_single_one_multi(),
+        };
+        match self.flatten.as_deref() {
+            None => builder.preserve_multiline(),
+            Some("") => builder.flatten_multiline(),
+            Some(separator) => builder.flatten_multiline_with(separator),
+        }
+    }
+
+    /// Builds `builder` with the configured dialect
+    ///
+    /// # Parameters
+    /// - `builder`: a builder from [`SqlDocConfig::builder`] with its sources added
+    ///
+    /// # Errors
+    /// - Will return [`DocError::UnknownDialect`] if the configured dialect is unknown
+    /// - Will return the same errors as [`SqlDocBuilder::build`]
+    pub fn build(&self, builder: SqlDocBuilder<'_>) -> Result<SqlDoc, DocError> {
+        builder.build_with_dialect_name(&self.dialect)
+    }
+
+    /// Builds the [`SqlDoc`] for the SQL files under `root`
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlDocConfig::build`]
+    pub fn build_dir<P: AsRef<Path> + ?Sized>(&self, root: &P) -> Result<SqlDoc, DocError> {
+        self.build(self.builder().add_dir(root))
+    }
+
+    /// Builds the [`SqlDoc`] for a single SQL file
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlDocConfig::build`]
+    pub fn build_path<P: AsRef<Path> + ?Sized>(&self, path: &P) -> Result<SqlDoc, DocError> {
+        self.build(self.builder().add_path(path))
+    }
+
+    /// Builds the [`SqlDoc`] for raw SQL text
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlDocConfig::build`]
+    pub fn build_str(&self, content: &str) -> Result<SqlDoc, DocError> {
+        self.build(self.builder().add_str(content))
+    }
+
+    /// Builds the [`SqlDoc`] for SQL read from `reader`, see [`SqlDoc::from_reader`]
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlDocConfig::build`]
+    pub fn build_reader<R: io::Read>(
+        &self,
+        reader: R,
+        path: Option<&Path>,
+    ) -> Result<SqlDoc, DocError> {
+        self.build(self.builder().add_reader(reader, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, sync::Arc, thread};
+
+    use crate::{
+        comments::LeadingCommentCapture, config::SqlDocConfig, error::DocError, files::ScanOptions,
+    };
+
+    #[test]
+    fn test_config_is_owned_and_shareable() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<SqlDocConfig>();
+    }
+
+    #[test]
+    fn test_config_builds_repeatedly_across_threads() -> Result<(), Box<dyn std::error::Error>> {
+        let config = Arc::new(SqlDocConfig {
+            leading: LeadingCommentCapture::AllLeading,
+            flatten: Some(". ".to_owned()),
+            ..SqlDocConfig::default()
+        });
+        let handles: Vec<_> = ["users", "posts"]
+            .into_iter()
+            .map(|name| {
+                let config = Arc::clone(&config);
+                thread::spawn(move || {
+                    let sql = format!("-- First\n-- Second\nCREATE TABLE {name} (id INTEGER);");
+                    config.build_str(&sql).map(|doc| doc.tables()[0].doc().map(str::to_owned))
+                })
+            })
+            .collect();
+        for handle in handles {
+            let doc = handle.join().unwrap_or_else(|_| panic!("build thread panicked"))?;
+            assert_eq!(doc.as_deref(), Some("First. Second"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_applies_scan_deny_and_dialect() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("config_applies_scan_deny_and_dialect");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("archive"))?;
+        fs::write(base.join("users.sql"), "CREATE TABLE users (id SERIAL);")?;
+        fs::write(base.join("skip.sql"), "CREATE TABLE skip (")?;
+        fs::write(base.join("archive/old.sql"), "CREATE TABLE old (")?;
+        let config = SqlDocConfig {
+            dialect: "postgres".to_owned(),
+            scan: ScanOptions::new().exclude("archive/**"),
+            deny: vec![base.join("skip.sql").to_string_lossy().into_owned()],
+            ..SqlDocConfig::default()
+        };
+        assert_eq!(config.build_dir(&base)?.number_of_tables(), 1);
+        assert_eq!(config.build_path(&base.join("users.sql"))?.number_of_tables(), 1);
+
+        let unknown = SqlDocConfig { dialect: "cobol".to_owned(), ..config };
+        assert!(matches!(unknown.build_str("SELECT 1;"), Err(DocError::UnknownDialect { .. })));
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+}
@@ -1,32 +1,102 @@
 //! Convert parsed SQL + extracted comments into structured documentation types.
 
 use core::fmt;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
 
-use sqlparser::ast::{Ident, ObjectName, ObjectNamePart, Spanned, Statement};
+use sqlparser::ast::{
+    ColumnDef, ColumnOption, DataType, Expr, ForeignKeyConstraint, Ident, IndexColumn, ObjectName,
+    ObjectNamePart, PrimaryKeyConstraint, Spanned, Statement, TableConstraint,
+};
 
 use crate::{
     ast::ParsedSqlFile,
-    comments::{Comments, LeadingCommentCapture, MultiFlatten},
+    comments::{Comment, Comments, LeadingCommentCapture, Location, MultiFlatten},
     error::DocError,
+    warnings::DocWarning,
 };
 
-/// Structure for containing the `name` of the `Column` and an [`Option`] for
-/// the comment as a [`String`]
+/// Structure for containing the `name` of the `Column`, an [`Option`] for
+/// the comment as a [`String`], and what its definition declares: the data
+/// type, nullability, default and keys
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColumnDoc {
     name: String,
     doc: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    data_type: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default = "nullable_by_default"))]
+    nullable: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    default: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    primary_key: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    foreign_key: Option<ForeignKey>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    ordinal: usize,
 }
 impl ColumnDoc {
-    /// Creates a new [`ColumnDoc`]
+    /// Creates a new [`ColumnDoc`] for a nullable column without a declared
+    /// type, default or keys
     ///
     /// # Parameters
     /// - name: `String` - the name of the column
     /// - doc: `Option<String>` the comment for the column
     #[must_use]
     pub const fn new(name: String, doc: Option<String>) -> Self {
-        Self { name, doc }
+        Self {
+            name,
+            doc,
+            data_type: None,
+            nullable: true,
+            default: None,
+            primary_key: false,
+            foreign_key: None,
+            ordinal: 0,
+        }
+    }
+
+    /// Sets the declared data type, e.g. `VARCHAR(255)`
+    #[must_use]
+    pub fn with_data_type(mut self, data_type: impl Into<String>) -> Self {
+        self.data_type = Some(data_type.into());
+        self
+    }
+
+    /// Sets whether the column accepts `NULL`
+    #[must_use]
+    pub const fn with_nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+
+    /// Sets the `DEFAULT` expression as written in SQL, e.g. `CURRENT_TIMESTAMP`
+    #[must_use]
+    pub fn with_default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    /// Sets whether the column is part of the table's primary key; a primary
+    /// key column is never nullable
+    #[must_use]
+    pub const fn with_primary_key(mut self, primary_key: bool) -> Self {
+        self.primary_key = primary_key;
+        if primary_key {
+            self.nullable = false;
+        }
+        self
+    }
+
+    /// Sets the column the column references as a foreign key
+    #[must_use]
+    pub fn with_foreign_key(mut self, foreign_key: ForeignKey) -> Self {
+        self.foreign_key = Some(foreign_key);
+        self
     }
 
     /// Getter for the `name` field
@@ -45,6 +115,105 @@ impl ColumnDoc {
     pub fn set_doc(&mut self, doc: impl Into<String>) {
         self.doc = Some(doc.into());
     }
+
+    /// Getter for the declared data type, `None` if the column has none
+    #[must_use]
+    pub fn data_type(&self) -> Option<&str> {
+        self.data_type.as_deref()
+    }
+
+    /// Whether the column accepts `NULL`, i.e. it is neither `NOT NULL` nor
+    /// part of the primary key
+    #[must_use]
+    pub const fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    /// Getter for the `DEFAULT` expression, if one is declared
+    #[must_use]
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Whether the column is part of the table's primary key
+    #[must_use]
+    pub const fn is_primary_key(&self) -> bool {
+        self.primary_key
+    }
+
+    /// Getter for the column's foreign key, if it references another table
+    #[must_use]
+    pub const fn foreign_key(&self) -> Option<&ForeignKey> {
+        self.foreign_key.as_ref()
+    }
+
+    /// The 1-based position of the column in its table's definition, or `0`
+    /// if the column does not belong to a [`TableDoc`]
+    #[must_use]
+    pub const fn ordinal(&self) -> usize {
+        self.ordinal
+    }
+}
+
+/// Default for [`ColumnDoc`]s deserialized without a `nullable` field
+#[cfg(feature = "serde")]
+const fn nullable_by_default() -> bool {
+    true
+}
+
+/// The target of a foreign key declared with `REFERENCES` or a
+/// `FOREIGN KEY` table constraint
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForeignKey {
+    schema: Option<String>,
+    table: String,
+    column: Option<String>,
+}
+
+impl ForeignKey {
+    /// Creates a new [`ForeignKey`]
+    ///
+    /// # Parameters
+    /// - schema: `Option<String>` - the schema of the referenced table, if qualified
+    /// - table: `String` - the name of the referenced table
+    /// - column: `Option<String>` - the referenced column, `None` for the
+    ///   referenced table's primary key
+    #[must_use]
+    pub const fn new(schema: Option<String>, table: String, column: Option<String>) -> Self {
+        Self { schema, table, column }
+    }
+
+    /// Getter for the schema of the referenced table
+    #[must_use]
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_deref()
+    }
+
+    /// Getter for the name of the referenced table
+    #[must_use]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Getter for the referenced column, `None` for the primary key
+    #[must_use]
+    pub fn column(&self) -> Option<&str> {
+        self.column.as_deref()
+    }
+}
+
+impl fmt::Display for ForeignKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(schema) = self.schema() {
+            write!(f, "{schema}.")?;
+        }
+        write!(f, "{}", self.table())?;
+        if let Some(column) = self.column() {
+            write!(f, ".{column}")?;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for ColumnDoc {
@@ -62,24 +231,29 @@ impl fmt::Display for ColumnDoc {
 /// Structure for containing the `name` of the `Table`, an [`Option`] for if the
 /// table has a schema,  an [`Option`] for the comment as a [`String`], and a
 /// `Vec` of [`ColumnDoc`] contained in the table
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableDoc {
     schema: Option<String>,
     name: String,
     doc: Option<String>,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_sorted_columns"))]
     columns: Vec<ColumnDoc>,
     path: Option<PathBuf>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    line: Option<u64>,
 }
 
 impl TableDoc {
-    /// Creates a new [`TableDoc`] after sorting [`ColumnDoc`] by `name`
+    /// Creates a new [`TableDoc`] after numbering the [`ColumnDoc`] by their
+    /// position in `columns` and sorting them by `name`
     ///
     /// # Parameters
     /// - name: `String` - the name of the table
     /// - doc: `Option<String>` of the comment for table
-    /// - columns: the `Vec<ColumnDoc>` of all [`ColumnDoc`] for this table
+    /// - columns: the `Vec<ColumnDoc>` of all [`ColumnDoc`] for this table, in
+    ///   the order they are defined
     #[must_use]
-    #[allow(clippy::missing_const_for_fn)]
     pub fn new(
         schema: Option<String>,
         name: String,
@@ -87,8 +261,18 @@ impl TableDoc {
         mut columns: Vec<ColumnDoc>,
         path: Option<PathBuf>,
     ) -> Self {
+        for (index, column) in columns.iter_mut().enumerate() {
+            column.ordinal = index + 1;
+        }
         columns.sort_by(|a, b| a.name().cmp(b.name()));
-        Self { schema, name, doc, columns, path }
+        Self { schema, name, doc, columns, path, line: None }
+    }
+
+    /// Sets the line the table's `CREATE TABLE` statement starts on
+    #[must_use]
+    pub const fn with_line(mut self, line: u64) -> Self {
+        self.line = Some(line);
+        self
     }
 
     /// Getter for the `Schema` of the table (if there is one)
@@ -103,6 +287,12 @@ impl TableDoc {
         &self.name
     }
 
+    /// The name qualified with the schema, e.g. `analytics.events`
+    #[must_use]
+    pub fn qualified_name(&self) -> String {
+        self.schema().map_or_else(|| self.name.clone(), |schema| format!("{schema}.{}", self.name))
+    }
+
     /// Getter for the `doc` field
     #[must_use]
     pub fn doc(&self) -> Option<&str> {
@@ -125,6 +315,14 @@ impl TableDoc {
         &self.columns
     }
 
+    /// Returns the columns in the order they are defined, see [`ColumnDoc::ordinal`]
+    #[must_use]
+    pub fn columns_in_order(&self) -> Vec<&ColumnDoc> {
+        let mut columns: Vec<&ColumnDoc> = self.columns.iter().collect();
+        columns.sort_by_key(|column| column.ordinal());
+        columns
+    }
+
     /// Getter that returns a mutable reference to the [`ColumnDoc`] vec
     pub fn columns_mut(&mut self) -> &mut [ColumnDoc] {
         &mut self.columns
@@ -157,6 +355,13 @@ impl TableDoc {
         self.path.as_deref()
     }
 
+    /// Getter for the line the table's `CREATE TABLE` statement starts on,
+    /// `None` if it is unknown
+    #[must_use]
+    pub const fn line(&self) -> Option<u64> {
+        self.line
+    }
+
     /// Returns the number of [`ColumnDoc`]
     #[must_use]
     pub fn number_of_columns(&self) -> usize {
@@ -164,6 +369,17 @@ impl TableDoc {
     }
 }
 
+// The line is where the table was found, not part of what it documents
+impl PartialEq for TableDoc {
+    fn eq(&self, other: &Self) -> bool {
+        self.schema == other.schema
+            && self.name == other.name
+            && self.doc == other.doc
+            && self.columns == other.columns
+            && self.path == other.path
+    }
+}
+
 impl fmt::Display for TableDoc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(s) = self.schema() {
@@ -188,6 +404,18 @@ impl fmt::Display for TableDoc {
     }
 }
 
+/// Deserializes the columns of a [`TableDoc`], restoring the sort order that
+/// [`TableDoc::column`] relies on
+#[cfg(feature = "serde")]
+fn deserialize_sorted_columns<'de, D>(deserializer: D) -> Result<Vec<ColumnDoc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut columns: Vec<ColumnDoc> = serde::Deserialize::deserialize(deserializer)?;
+    columns.sort_by(|a, b| a.name().cmp(b.name()));
+    Ok(columns)
+}
+
 /// Structure for containing the docs for every `Table` in an `.sql` file as a
 /// `Vec` of [`TableDoc`]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -224,37 +452,95 @@ impl SqlFileDoc {
         capture: LeadingCommentCapture,
         flatten: MultiFlatten,
     ) -> Result<Self, DocError> {
+        Self::from_parsed_file_with_warnings(file, comments, capture, flatten).map(|(doc, _)| doc)
+    }
+
+    /// Same as [`SqlFileDoc::from_parsed_file`], but also returns a
+    /// [`DocWarning`] for every comment that was orphaned, attached to more
+    /// than one object, or attached to a column from before its statement.
+    ///
+    /// Warnings are ordered by the position of the comment they refer to.
+    ///
+    /// # Parameters
+    /// - `file`: the [`ParsedSqlFile`]
+    /// - `comments`: the parsed [`Comments`]
+    ///
+    /// # Errors
+    /// - Returns [`DocError::InvalidObjectName`] if the table name has no identifier components.
+    pub fn from_parsed_file_with_warnings(
+        file: &ParsedSqlFile,
+        comments: &Comments,
+        capture: LeadingCommentCapture,
+        flatten: MultiFlatten,
+    ) -> Result<(Self, Vec<DocWarning>), DocError> {
         let mut tables = Vec::new();
+        let mut warnings = Vec::new();
+        let mut object_lines = Vec::new();
+        let mut attachments: BTreeMap<(u64, u64), (Comment, Vec<String>)> = BTreeMap::new();
+        let mut attach = |comment: &Comment, target: String| {
+            let start = comment.span().start();
+            attachments
+                .entry((start.line(), start.column()))
+                .or_insert_with(|| (comment.clone(), Vec::new()))
+                .1
+                .push(target);
+        };
         for statement in file.statements() {
             #[allow(clippy::single_match)]
             match statement {
                 Statement::CreateTable(table) => {
-                    let table_start = table.span().start.line;
+                    let table_start = table.span().start;
+                    let statement_start = Location::new(table_start.line, table_start.column);
+                    let (schema, name) = schema_and_table(&table.name)?;
+                    let qualified_name =
+                        schema.as_ref().map_or_else(|| name.clone(), |s| format!("{s}.{name}"));
+                    object_lines.push(table_start.line);
+                    let table_leading = comments
+                        .leading_comments(table_start.line, capture)
+                        .collapse_comments(flatten);
                     let mut column_docs = Vec::new();
                     for column in &table.columns {
                         let column_start = column.span().start.line;
+                        object_lines.push(column_start);
                         let column_leading = comments
                             .leading_comments(column_start, capture)
                             .collapse_comments(flatten);
                         let column_name = column.name.value.clone();
+                        if let Some(comment) = &column_leading {
+                            // the table's own comment also documents a column on
+                            // its first line, which is reported as ambiguous
+                            let leads_table = table_leading.as_ref() == Some(comment);
+                            if *comment.span().start() < statement_start && !leads_table {
+                                warnings.push(DocWarning::UnrelatedColumnComment {
+                                    path: file.path_into_path_buf(),
+                                    table: qualified_name.clone(),
+                                    column: column_name.clone(),
+                                    comment: comment.clone(),
+                                });
+                            } else {
+                                attach(comment, format!("{qualified_name}.{column_name}"));
+                            }
+                        }
                         let column_doc = match column_leading {
                             Some(col_comment) => {
                                 ColumnDoc::new(column_name, Some(col_comment.text().to_owned()))
                             }
                             None => ColumnDoc::new(column_name, None),
                         };
-                        column_docs.push(column_doc);
+                        column_docs.push(with_definition(column_doc, column)?);
                     }
-                    let table_leading =
-                        comments.leading_comments(table_start, capture).collapse_comments(flatten);
-                    let (schema, name) = schema_and_table(&table.name)?;
+                    if let Some(comment) = &table_leading {
+                        attach(comment, qualified_name);
+                    }
+                    apply_constraints(&table.constraints, &mut column_docs)?;
                     let table_doc = TableDoc::new(
                         schema,
                         name,
                         table_leading.as_ref().map(|c| c.text().to_owned()),
                         column_docs,
                         file.path_into_path_buf(),
-                    );
+                    )
+                    .with_line(table_start.line);
                     tables.push(table_doc);
                 }
                 // can add support for other types of statements below
@@ -262,7 +548,24 @@ impl SqlFileDoc {
             }
         }
 
-        Ok(Self { tables })
+        warnings.extend(attachments.into_values().filter(|(_, targets)| targets.len() > 1).map(
+            |(comment, targets)| DocWarning::AmbiguousComment {
+                path: file.path_into_path_buf(),
+                comment,
+                targets,
+            },
+        ));
+        warnings.extend(
+            orphaned_comments(file.content(), comments, &object_lines).into_iter().map(|comment| {
+                DocWarning::OrphanedComment { path: file.path_into_path_buf(), comment }
+            }),
+        );
+        warnings.sort_by_key(|w| {
+            let start = w.comment().span().start();
+            (start.line(), start.column())
+        });
+
+        Ok((Self { tables }, warnings))
     }
 
     /// Getter function to get a slice of [`TableDoc`]
@@ -298,6 +601,125 @@ impl IntoIterator for SqlFileDoc {
     }
 }
 
+/// Finds the comments that stand on their own line(s) but are not part of the
+/// leading comment block of any table or column starting on `object_lines`.
+fn orphaned_comments(content: &str, comments: &Comments, object_lines: &[u64]) -> Vec<Comment> {
+    let mut claimed = BTreeSet::new();
+    for line in object_lines {
+        let leading = comments.leading_comments(*line, LeadingCommentCapture::AllLeading);
+        claimed.extend(leading.comments().iter().map(|c| {
+            let start = c.span().start();
+            (start.line(), start.column())
+        }));
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    comments
+        .comments()
+        .iter()
+        .filter(|c| {
+            let start = c.span().start();
+            !claimed.contains(&(start.line(), start.column())) && stands_alone(c, &lines)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether nothing but whitespace surrounds `comment` on the lines it spans
+fn stands_alone(comment: &Comment, lines: &[&str]) -> bool {
+    let line_text = |line: u64| {
+        usize::try_from(line)
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| lines.get(i))
+            .copied()
+            .unwrap_or_default()
+    };
+    let (start, end) = (comment.span().start(), comment.span().end());
+    let before = usize::try_from(start.column().saturating_sub(1)).unwrap_or(usize::MAX);
+    let after = usize::try_from(end.column()).unwrap_or(usize::MAX);
+    line_text(start.line()).chars().take(before).all(char::is_whitespace)
+        && line_text(end.line()).chars().skip(after).all(char::is_whitespace)
+}
+
+/// Adds the data type, nullability, default and keys declared in the
+/// definition of `column` to its `doc`
+///
+/// # Errors
+/// - Returns [`DocError::InvalidObjectName`] if a referenced table name has no identifier components.
+fn with_definition(doc: ColumnDoc, column: &ColumnDef) -> Result<ColumnDoc, DocError> {
+    let mut doc = match &column.data_type {
+        DataType::Unspecified => doc,
+        data_type => doc.with_data_type(data_type.to_string()),
+    };
+    for option in &column.options {
+        doc = match &option.option {
+            ColumnOption::Null => doc.with_nullable(true),
+            ColumnOption::NotNull => doc.with_nullable(false),
+            ColumnOption::Default(expr) => doc.with_default(expr.to_string()),
+            ColumnOption::PrimaryKey(_) => doc.with_primary_key(true),
+            ColumnOption::ForeignKey(ForeignKeyConstraint {
+                foreign_table,
+                referred_columns,
+                ..
+            }) => {
+                let (schema, table) = schema_and_table(foreign_table)?;
+                let column = referred_columns.first().map(|c| c.value.clone());
+                doc.with_foreign_key(ForeignKey::new(schema, table, column))
+            }
+            _ => doc,
+        };
+    }
+    Ok(doc)
+}
+
+/// Marks the columns named in `PRIMARY KEY` and `FOREIGN KEY` table
+/// constraints
+///
+/// # Errors
+/// - Returns [`DocError::InvalidObjectName`] if a referenced table name has no identifier components.
+fn apply_constraints(
+    constraints: &[TableConstraint],
+    columns: &mut [ColumnDoc],
+) -> Result<(), DocError> {
+    for constraint in constraints {
+        match constraint {
+            TableConstraint::PrimaryKey(PrimaryKeyConstraint { columns: keys, .. }) => {
+                for key in keys.iter().filter_map(index_column_name) {
+                    if let Some(column) = columns.iter_mut().find(|c| c.name() == key) {
+                        column.primary_key = true;
+                        column.nullable = false;
+                    }
+                }
+            }
+            TableConstraint::ForeignKey(ForeignKeyConstraint {
+                columns: keys,
+                foreign_table,
+                referred_columns,
+                ..
+            }) => {
+                let (schema, table) = schema_and_table(foreign_table)?;
+                for (index, key) in keys.iter().enumerate() {
+                    if let Some(column) = columns.iter_mut().find(|c| c.name() == key.value) {
+                        let referred = referred_columns.get(index).map(|c| c.value.clone());
+                        column.foreign_key =
+                            Some(ForeignKey::new(schema.clone(), table.clone(), referred));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The name of a column listed in a primary key, if it is a plain identifier
+fn index_column_name(column: &IndexColumn) -> Option<&str> {
+    match &column.column.expr {
+        Expr::Identifier(ident) => Some(&ident.value),
+        _ => None,
+    }
+}
+
 /// Helper function that will parse the table's schema and table name.
 /// Easily extensible for catalog if neeeded as well.
 ///
@@ -523,7 +945,6 @@ CREATE TABLE posts (
 
             let got = docs?;
             let file_path = file.file().path().ok_or("missing path")?;
-
             match filename {
                 "with_single_line_comments.sql" | "with_mixed_comments.sql" => {
                     let expected = with_path(expected_values[0].clone(), file_path);
@@ -544,16 +965,33 @@ CREATE TABLE posts (
         Ok(())
     }
 
+    /// Sets the path of every table in `doc` and declares its columns the way
+    /// the fixture SQL does
     fn with_path(mut doc: SqlFileDoc, path: &std::path::Path) -> SqlFileDoc {
         let pb = path.to_path_buf();
 
         for table in doc.tables_mut() {
             table.set_path(Some(pb.clone()));
+            for column in table.columns_mut() {
+                *column = with_definition(column.clone());
+            }
         }
 
         doc
     }
 
+    fn with_definition(column: ColumnDoc) -> ColumnDoc {
+        match column.name() {
+            "id" => column.with_data_type("INTEGER").with_primary_key(true),
+            "username" | "email" | "title" => {
+                column.with_data_type("VARCHAR(255)").with_nullable(false)
+            }
+            "user_id" => column.with_data_type("INTEGER").with_nullable(false),
+            "body" => column.with_data_type("TEXT").with_nullable(false),
+            _ => column.with_data_type("TIMESTAMP").with_default("CURRENT_TIMESTAMP"),
+        }
+    }
+
     fn expected_without_comments_docs() -> SqlFileDoc {
         SqlFileDoc::new(vec![
             TableDoc::new(
@@ -832,6 +1270,42 @@ CREATE TABLE posts (
         }
     }
 
+    #[test]
+    fn test_column_definitions_and_keys() -> Result<(), Box<dyn std::error::Error>> {
+        let sql = "
+CREATE TABLE app.orders (
+    id INTEGER,
+    user_id INTEGER NOT NULL REFERENCES app.users (id),
+    sku TEXT DEFAULT 'none',
+    note TEXT NULL REFERENCES notes,
+    PRIMARY KEY (id),
+    FOREIGN KEY (sku) REFERENCES products (code)
+);";
+        let doc = crate::SqlDoc::builder_from_str(sql).build::<GenericDialect>()?;
+        let table = doc.table("orders", Some("app"))?;
+        assert_eq!(table.qualified_name(), "app.orders");
+        assert_eq!(table.line(), Some(2));
+        let order: Vec<&str> = table.columns_in_order().iter().map(|c| c.name()).collect();
+        assert_eq!(order, ["id", "user_id", "sku", "note"]);
+
+        let id = table.column("id")?;
+        assert!(id.is_primary_key() && !id.is_nullable());
+        assert_eq!((id.data_type(), id.ordinal()), (Some("INTEGER"), 1));
+        let user_id = table.column("user_id")?;
+        assert!(!user_id.is_nullable() && !user_id.is_primary_key());
+        let key = user_id.foreign_key().ok_or("missing foreign key")?;
+        assert_eq!((key.schema(), key.table(), key.column()), (Some("app"), "users", Some("id")));
+        assert_eq!(key.to_string(), "app.users.id");
+        let sku = table.column("sku")?;
+        assert_eq!(sku.default(), Some("'none'"));
+        assert!(sku.is_nullable());
+        let key = sku.foreign_key().ok_or("missing foreign key")?;
+        assert_eq!((key.schema(), key.table(), key.column()), (None, "products", Some("code")));
+        let key = table.column("note")?.foreign_key().ok_or("missing foreign key")?;
+        assert_eq!((key.table(), key.column()), ("notes", None));
+        Ok(())
+    }
+
     #[test]
     fn column_doc_set_doc_updates_doc() {
         let mut col = ColumnDoc::new("id".to_owned(), None);
@@ -937,4 +1411,107 @@ CREATE TABLE posts (
         assert!(missing.is_err());
         assert!(matches!(missing, Err(DocError::ColumnNotFound { name }) if name == "nope"));
     }
+
+    #[test]
+    fn test_from_parsed_file_with_warnings_flags_suspicious_comments()
+    -> Result<(), Box<dyn std::error::Error>> {
+        use crate::{
+            ast::ParsedSqlFile,
+            comments::{Comments, LeadingCommentCapture, MultiFlatten},
+            source::SqlSource,
+            warnings::DocWarning,
+        };
+
+        let sql = "\
+-- file header, followed by a blank line
+
+-- Users table
+CREATE TABLE users (id INTEGER PRIMARY KEY);
+CREATE TABLE pairs (
+    -- shared by both columns
+    a INTEGER, b INTEGER, /* inline, ignored */
+    -- documented column
+    c INTEGER
+);
+-- about to be dropped
+DROP TABLE old_things;
+CREATE TABLE a (x INTEGER); /* about a,
+continued */ CREATE TABLE b (
+    y INTEGER
+);
+";
+        let parsed = ParsedSqlFile::parse::<GenericDialect>(SqlSource::from_str(sql.into(), None))?;
+        let comments = Comments::parse_all_comments_from_file(&parsed)?;
+        let (doc, warnings) = SqlFileDoc::from_parsed_file_with_warnings(
+            &parsed,
+            &comments,
+            LeadingCommentCapture::SingleNearest,
+            MultiFlatten::NoFlat,
+        )?;
+        assert_eq!(doc.number_of_tables(), 4);
+        let users = doc.tables().iter().find(|t| t.name() == "users").ok_or("users missing")?;
+        assert_eq!(users.doc(), Some("Users table"));
+        assert_eq!(users.column("id")?.doc(), Some("Users table"));
+
+        let summary: Vec<(u64, &str)> = warnings
+            .iter()
+            .map(|w| {
+                let kind = match w {
+                    DocWarning::OrphanedComment { .. } => "orphaned",
+                    DocWarning::AmbiguousComment { .. } => "ambiguous",
+                    DocWarning::UnrelatedColumnComment { .. } => "unrelated",
+                };
+                (w.comment().span().start().line(), kind)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "orphaned"),
+                (3, "ambiguous"),
+                (6, "ambiguous"),
+                (11, "orphaned"),
+                (13, "unrelated")
+            ]
+        );
+
+        let Some(DocWarning::AmbiguousComment { targets, .. }) = warnings.get(1) else {
+            panic!("expected an ambiguous comment warning, got {warnings:?}");
+        };
+        assert_eq!(targets, &vec!["users.id".to_owned(), "users".to_owned()]);
+        let Some(DocWarning::AmbiguousComment { targets, .. }) = warnings.get(2) else {
+            panic!("expected an ambiguous comment warning, got {warnings:?}");
+        };
+        assert_eq!(targets, &vec!["pairs.a".to_owned(), "pairs.b".to_owned()]);
+        let Some(DocWarning::UnrelatedColumnComment { table, column, .. }) = warnings.get(4) else {
+            panic!("expected an unrelated column comment warning, got {warnings:?}");
+        };
+        assert_eq!((table.as_str(), column.as_str()), ("b", "y"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_parsed_file_with_warnings_quiet_for_well_placed_comments()
+    -> Result<(), Box<dyn std::error::Error>> {
+        use crate::{
+            ast::ParsedSqlFile,
+            comments::{Comments, LeadingCommentCapture, MultiFlatten},
+            source::SqlSource,
+        };
+
+        let parsed = ParsedSqlFile::parse::<GenericDialect>(SqlSource::from_str(
+            mixed_comments_sql()
+                .replace("-- interstitial Comment above statements (should be ignored)\n", ""),
+            None,
+        ))?;
+        let comments = Comments::parse_all_comments_from_file(&parsed)?;
+        let (_, warnings) = SqlFileDoc::from_parsed_file_with_warnings(
+            &parsed,
+            &comments,
+            LeadingCommentCapture::SingleNearest,
+            MultiFlatten::NoFlat,
+        )?;
+        assert!(warnings.is_empty(), "unexpected warnings: {warnings:?}");
+        Ok(())
+    }
 }
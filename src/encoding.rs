@@ -0,0 +1,193 @@
+//! Decode the raw bytes of SQL sources into text.
+//!
+//! Files are expected to be UTF-8 unless they start with a byte order mark or
+//! an explicit [`Encoding`] is given. The byte order mark of the encoding a
+//! source is decoded in is stripped, so that spans start at line 1, column 1
+//! on the first real character. Latin-1 has no byte order mark, so its bytes
+//! are always decoded as they are.
+
+use core::fmt;
+use std::io;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// The text encodings SQL sources can be read in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// UTF-8, the default
+    Utf8,
+    /// UTF-16 little endian, as exported by SQL Server Management Studio
+    Utf16Le,
+    /// UTF-16 big endian
+    Utf16Be,
+    /// ISO-8859-1, where every byte is the code point of the same value
+    Latin1,
+}
+
+impl Encoding {
+    /// Detects the encoding of `bytes` from its byte order mark, returning
+    /// `None` if there is none
+    ///
+    /// # Parameters
+    /// - `bytes`: the raw content of a source
+    #[must_use]
+    pub fn from_bom(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(UTF8_BOM) {
+            Some(Self::Utf8)
+        } else if bytes.starts_with(UTF16LE_BOM) {
+            Some(Self::Utf16Le)
+        } else if bytes.starts_with(UTF16BE_BOM) {
+            Some(Self::Utf16Be)
+        } else {
+            None
+        }
+    }
+
+    /// Looks up an [`Encoding`] by a common name such as `"utf-8"`,
+    /// `"utf-16le"` or `"latin1"`, case-insensitively
+    ///
+    /// # Parameters
+    /// - `name`: the encoding name, e.g. as read from a configuration file
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf8" | "utf-8" => Some(Self::Utf8),
+            "utf16" | "utf-16" | "utf16le" | "utf-16le" => Some(Self::Utf16Le),
+            "utf16be" | "utf-16be" => Some(Self::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" => Some(Self::Latin1),
+            _ => None,
+        }
+    }
+
+    /// Decodes `bytes`, stripping this encoding's byte order mark if present
+    ///
+    /// # Parameters
+    /// - `bytes`: the raw content of a source
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] of kind [`io::ErrorKind::InvalidData`] if
+    ///   `bytes` are not valid in this encoding
+    pub fn decode(self, bytes: &[u8]) -> io::Result<String> {
+        match self {
+            Self::Utf8 => {
+                let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+                String::from_utf8(bytes.to_vec()).map_err(|e| {
+                    invalid_data(format!(
+                        "invalid UTF-8 at byte {}; set the source encoding, e.g. \
+                         `Encoding::Latin1`, if the file is not UTF-8",
+                        e.utf8_error().valid_up_to()
+                    ))
+                })
+            }
+            Self::Utf16Le | Self::Utf16Be => {
+                let little_endian = self == Self::Utf16Le;
+                let bom = if little_endian { UTF16LE_BOM } else { UTF16BE_BOM };
+                let bytes = bytes.strip_prefix(bom).unwrap_or(bytes);
+                if bytes.len() % 2 != 0 {
+                    return Err(invalid_data(format!("{self} content has an odd number of bytes")));
+                }
+                let units = bytes.chunks_exact(2).map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if little_endian { u16::from_le_bytes(pair) } else { u16::from_be_bytes(pair) }
+                });
+                char::decode_utf16(units).collect::<Result<String, _>>().map_err(|e| {
+                    invalid_data(format!(
+                        "{self} content has an unpaired surrogate {:#06x}",
+                        e.unpaired_surrogate()
+                    ))
+                })
+            }
+            Self::Latin1 => Ok(bytes.iter().copied().map(char::from).collect()),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utf8 => write!(f, "UTF-8"),
+            Self::Utf16Le => write!(f, "UTF-16LE"),
+            Self::Utf16Be => write!(f, "UTF-16BE"),
+            Self::Latin1 => write!(f, "Latin-1"),
+        }
+    }
+}
+
+/// Decodes the raw content of a source with `encoding`, or with the encoding
+/// named by its byte order mark, falling back to UTF-8
+///
+/// # Parameters
+/// - `bytes`: the raw content of a source
+/// - `encoding`: the explicit [`Encoding`] of the source, if known
+///
+/// # Errors
+/// - Returns an [`io::Error`] of kind [`io::ErrorKind::InvalidData`] if
+///   `bytes` are not valid in the chosen encoding
+pub fn decode(bytes: &[u8], encoding: Option<Encoding>) -> io::Result<String> {
+    encoding.or_else(|| Encoding::from_bom(bytes)).unwrap_or(Encoding::Utf8).decode(bytes)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::encoding::{Encoding, decode};
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        let mut bytes = if little_endian { vec![0xFF, 0xFE] } else { vec![0xFE, 0xFF] };
+        for unit in text.encode_utf16() {
+            let pair = if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() };
+            bytes.extend(pair);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode_detects_bom_and_strips_it() -> Result<(), io::Error> {
+        let sql = "-- Tabla de usuarios\nCREATE TABLE users (id INTEGER);";
+        let mut utf8 = vec![0xEF, 0xBB, 0xBF];
+        utf8.extend(sql.as_bytes());
+        assert_eq!(decode(&utf8, None)?, sql);
+        assert_eq!(decode(&utf16(sql, true), None)?, sql);
+        assert_eq!(decode(&utf16(sql, false), None)?, sql);
+        assert_eq!(decode(sql.as_bytes(), None)?, sql);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_with_explicit_encoding() -> Result<(), io::Error> {
+        let latin1 = b"-- Caf\xe9\nCREATE TABLE t (id INTEGER);";
+        let err = decode(latin1, None).err().unwrap_or_else(|| panic!("expected an error"));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("invalid UTF-8 at byte 6"));
+        assert_eq!(
+            decode(latin1, Some(Encoding::Latin1))?,
+            "-- Café\nCREATE TABLE t (id INTEGER);"
+        );
+
+        let without_bom = utf16("SELECT 1;", true).split_off(2);
+        assert_eq!(decode(&without_bom, Some(Encoding::Utf16Le))?, "SELECT 1;");
+        assert_eq!(decode(&utf16("SELECT 1;", false), Some(Encoding::Utf16Be))?, "SELECT 1;");
+        assert_eq!(decode(b"\xEF\xBB\xBFSELECT 1;", Some(Encoding::Utf8))?, "SELECT 1;");
+        assert_eq!(decode(b"\xEF\xBB\xBFSELECT 1;", Some(Encoding::Latin1))?, "ï»¿SELECT 1;");
+        assert!(Encoding::Utf16Le.decode(&[0x41]).is_err());
+        assert!(Encoding::Utf16Le.decode(&[0x00, 0xD8]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoding_from_name() {
+        assert_eq!(Encoding::from_name("UTF-8"), Some(Encoding::Utf8));
+        assert_eq!(Encoding::from_name("utf_16le"), Some(Encoding::Utf16Le));
+        assert_eq!(Encoding::from_name("UTF-16BE"), Some(Encoding::Utf16Be));
+        assert_eq!(Encoding::from_name(" ISO-8859-1 "), Some(Encoding::Latin1));
+        assert_eq!(Encoding::from_name("ebcdic"), None);
+        assert_eq!(Encoding::Latin1.to_string(), "Latin-1");
+    }
+}
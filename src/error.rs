@@ -1,12 +1,18 @@
 //! Error types returned by this crate’s public APIs.
 
 use crate::{
-    comments::CommentError,
+    ast::{dialect_from_name, parse_error_span},
+    comments::{CommentError, Location, Span},
     docs::{ColumnDoc, TableDoc},
+    source::SqlSource,
 };
 use core::fmt;
-use sqlparser::parser::ParserError;
-use std::{error, fmt::Debug};
+use sqlparser::{dialect::GenericDialect, parser::ParserError};
+use std::{
+    error,
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
 
 /// Errors that can occur while discovering, parsing, or documenting SQL files.
 #[non_exhaustive]
@@ -54,6 +60,172 @@ pub enum DocError {
         /// the schema for the table not found
         schema: String,
     },
+    /// Wraps another [`DocError`] with the source it was raised for
+    InSource {
+        /// The path of the source, `None` if it was built from a string
+        path: Option<PathBuf>,
+        /// The [`Span`] of the offending text, if the error carries a location
+        span: Option<Span>,
+        /// The source line that `span` starts on, used to render a snippet
+        line_text: Option<String>,
+        /// The underlying error
+        error: Box<Self>,
+    },
+    /// No SQL dialect is known by the requested name
+    UnknownDialect {
+        /// The dialect name that could not be resolved
+        name: String,
+    },
+    /// No renderer is registered under the requested format name
+    UnknownFormat {
+        /// The format name that could not be resolved
+        name: String,
+    },
+    /// A JSON document could not be imported as a [`crate::SqlDoc`]
+    InvalidJson {
+        /// What is wrong with the document
+        message: String,
+        /// The line of the document the problem was found on, `0` if unknown
+        line: u64,
+        /// The column of the document the problem was found on, `0` if unknown
+        column: u64,
+    },
+    /// A template could not be loaded, parsed or rendered
+    InvalidTemplate {
+        /// The name of the template, if the error points into one
+        name: Option<String>,
+        /// What went wrong
+        message: String,
+        /// The line of the template the problem was found on, `0` if unknown
+        line: u64,
+    },
+    /// Every error encountered while building with [`crate::SqlDocBuilder::collect_all_errors`]
+    MultipleErrors {
+        /// The errors in the order their sources were processed
+        errors: Vec<Self>,
+    },
+}
+
+impl DocError {
+    /// Attaches the path and offending line of `source` to this error.
+    ///
+    /// A [`DocError::SqlParserError`] is located by re-parsing `source` with the
+    /// dialect named in its header, or the generic dialect if it names none.
+    /// Errors that already carry a source are returned unchanged.
+    ///
+    /// # Parameters
+    /// - `source`: the [`SqlSource`] that was being processed when the error occurred
+    #[must_use]
+    pub fn with_source(self, source: &SqlSource) -> Self {
+        let span = if matches!(self, Self::SqlParserError(_)) {
+            let dialect = source.dialect_directive().and_then(|name| dialect_from_name(name).ok());
+            parse_error_span(source.content(), dialect.as_deref().unwrap_or(&GenericDialect {}))
+        } else {
+            self.span()
+        };
+        self.with_source_span(source, span)
+    }
+
+    /// Attaches the path of `source` to this error along with `span`, for
+    /// errors that cannot locate the offending text themselves
+    #[must_use]
+    pub(crate) fn with_source_span(self, source: &SqlSource, span: Option<Span>) -> Self {
+        if matches!(self, Self::InSource { .. }) {
+            return self;
+        }
+        let line_text = span.and_then(|span| {
+            let index = usize::try_from(span.start().line()).ok()?.checked_sub(1)?;
+            source.content().lines().nth(index).map(ToOwned::to_owned)
+        });
+        Self::InSource { path: source.path_into_path_buf(), span, line_text, error: Box::new(self) }
+    }
+
+    /// Attaches a path to this error when no source content is available, such
+    /// as when the file could not be read.
+    ///
+    /// Errors that already carry a source are returned unchanged.
+    ///
+    /// # Parameters
+    /// - `path`: the path that was being processed when the error occurred
+    #[must_use]
+    pub fn with_path(self, path: &Path) -> Self {
+        if matches!(self, Self::InSource { .. }) {
+            return self;
+        }
+        Self::InSource {
+            path: Some(path.to_path_buf()),
+            span: self.span(),
+            line_text: None,
+            error: Box::new(self),
+        }
+    }
+
+    /// Returns the path of the source this error was raised for, if known
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::InSource { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`Span`] of the offending text, if the error carries a location
+    #[must_use]
+    pub const fn span(&self) -> Option<Span> {
+        match self {
+            Self::CommentError(CommentError::UnmatchedMultilineCommentStart { location }) => {
+                Some(token_span(*location, 2))
+            }
+            Self::CommentError(CommentError::UnterminatedMultiLineComment { start }) => {
+                Some(token_span(*start, 2))
+            }
+            Self::InvalidObjectName { line, column, .. } => {
+                Some(token_span(Location::new(*line, *column), 1))
+            }
+            Self::InSource { span, .. } => *span,
+            Self::FileReadError(_)
+            | Self::SqlParserError(_)
+            | Self::MultipleErrors { .. }
+            | Self::UnknownDialect { .. }
+            | Self::UnknownFormat { .. }
+            | Self::InvalidJson { .. }
+            | Self::InvalidTemplate { .. }
+            | Self::TableNotFound { .. }
+            | Self::ColumnNotFound { .. }
+            | Self::DuplicateTablesFound { .. }
+            | Self::DuplicateColumnsFound { .. }
+            | Self::TableWithSchemaNotFound { .. } => None,
+        }
+    }
+
+    /// Returns the [`Location`] where the offending text starts, if known
+    #[must_use]
+    pub fn location(&self) -> Option<Location> {
+        self.span().map(|span| *span.start())
+    }
+}
+
+/// Builds a single-line [`Span`] of `width` characters starting at `start`
+const fn token_span(start: Location, width: u64) -> Span {
+    Span::new(start, Location::new(start.line(), start.column() + width))
+}
+
+/// Writes the `line_text` with a gutter and a caret line underneath `span`
+fn write_snippet(f: &mut fmt::Formatter<'_>, span: Span, line_text: &str) -> fmt::Result {
+    let line = span.start().line();
+    let gutter = " ".repeat(line.to_string().len());
+    let skip = usize::try_from(span.start().column().saturating_sub(1)).unwrap_or(usize::MAX);
+    let width = if span.end().line() == line {
+        span.end().column().saturating_sub(span.start().column()).max(1)
+    } else {
+        1
+    };
+    let padding: String =
+        line_text.chars().take(skip).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    let carets = "^".repeat(usize::try_from(width).unwrap_or(1));
+    writeln!(f, "{gutter} |")?;
+    writeln!(f, "{line} | {line_text}")?;
+    write!(f, "{gutter} | {padding}{carets}")
 }
 
 impl fmt::Display for DocError {
@@ -86,6 +258,49 @@ impl fmt::Display for DocError {
             Self::TableWithSchemaNotFound { name, schema } => {
                 writeln!(f, "Table: {name} with schema: {schema} not found in SqlDoc")
             }
+            Self::InSource { path, span, line_text, error } => {
+                let display_path =
+                    path.as_deref().map_or_else(|| "<string>".into(), Path::to_string_lossy);
+                match span {
+                    Some(span) => write!(
+                        f,
+                        "{display_path}:{}:{}: {error}",
+                        span.start().line(),
+                        span.start().column()
+                    )?,
+                    None => write!(f, "{display_path}: {error}")?,
+                }
+                if let (Some(span), Some(line_text)) = (span, line_text) {
+                    writeln!(f)?;
+                    write_snippet(f, *span, line_text)?;
+                }
+                Ok(())
+            }
+            Self::UnknownDialect { name } => write!(f, "Unknown SQL dialect: {name}"),
+            Self::UnknownFormat { name } => write!(f, "Unknown output format: {name}"),
+            Self::InvalidJson { message, line: 0, .. } => {
+                write!(f, "invalid JSON document: {message}")
+            }
+            Self::InvalidJson { message, line, column } => {
+                write!(f, "invalid JSON document at line {line}, column {column}: {message}")
+            }
+            Self::InvalidTemplate { name, message, line } => {
+                write!(f, "template error")?;
+                if let Some(name) = name {
+                    write!(f, " in {name}")?;
+                }
+                if *line > 0 {
+                    write!(f, " at line {line}")?;
+                }
+                write!(f, ": {message}")
+            }
+            Self::MultipleErrors { errors } => {
+                write!(f, "{} error(s) found while building SqlDoc:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n\n{error}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -96,12 +311,18 @@ impl error::Error for DocError {
             Self::FileReadError(e) => Some(e),
             Self::CommentError(e) => Some(e),
             Self::SqlParserError(e) => Some(e),
+            Self::InSource { error, .. } => Some(error.as_ref()),
             Self::InvalidObjectName { .. }
             | Self::TableNotFound { .. }
             | Self::ColumnNotFound { .. }
             | Self::DuplicateTablesFound { .. }
             | Self::DuplicateColumnsFound { .. }
-            | Self::TableWithSchemaNotFound { .. } => None,
+            | Self::TableWithSchemaNotFound { .. }
+            | Self::MultipleErrors { .. }
+            | Self::InvalidJson { .. }
+            | Self::InvalidTemplate { .. }
+            | Self::UnknownDialect { .. }
+            | Self::UnknownFormat { .. } => None,
         }
     }
 }
@@ -306,4 +527,85 @@ mod tests {
         };
         assert!(e.source().is_none());
     }
+
+    #[test]
+    fn test_doc_error_with_source_renders_snippet_with_caret() {
+        use std::path::PathBuf;
+
+        use crate::{comments::Location, source::SqlSource};
+
+        let source = SqlSource::from_str(
+            "-- users\nCREATE TABLE users (\n    id INTEGER */\n);".to_owned(),
+            Some(PathBuf::from("schema/users.sql")),
+        );
+        let e = DocError::CommentError(CommentError::UnmatchedMultilineCommentStart {
+            location: Location::new(3, 16),
+        })
+        .with_source(&source);
+
+        assert_eq!(e.path(), Some(std::path::Path::new("schema/users.sql")));
+        assert_eq!(e.location(), Some(Location::new(3, 16)));
+        assert_eq!(
+            e.to_string(),
+            "schema/users.sql:3:16: comment parse error: unmatched block comment start at line 3, column 16\n  |\n3 |     id INTEGER */\n  |                ^^"
+        );
+    }
+
+    #[test]
+    fn test_doc_error_with_source_locates_parser_error() {
+        use crate::{comments::Location, source::SqlSource};
+
+        let source = SqlSource::from_str("SELECT 1;\n\tSELEC 2;".to_owned(), None);
+        let error = || {
+            DocError::SqlParserError(ParserError::ParserError(
+                "Expected: an SQL statement, found: SELEC at Line: 2, Column: 2".to_owned(),
+            ))
+        };
+        let e = error().with_source(&source);
+        assert_eq!(e.path(), None);
+        assert_eq!(e.location(), Some(Location::new(2, 2)));
+        let rendered = e.to_string();
+        assert!(rendered.starts_with("<string>:2:2: SQL parse error"), "output was: {rendered}");
+        assert!(rendered.ends_with("2 | \tSELEC 2;\n  | \t^^^^^^^"), "output was: {rendered}");
+
+        let mysql =
+            SqlSource::from_str("-- dialect: mysql\nSELECT `a` FROM t;\nSELEC 2;".to_owned(), None);
+        assert_eq!(error().with_source(&mysql).location(), Some(Location::new(3, 1)));
+    }
+
+    #[test]
+    fn test_doc_error_with_path_and_no_location() {
+        use std::{error::Error as _, path::Path};
+
+        let e = DocError::SqlParserError(ParserError::RecursionLimitExceeded)
+            .with_path(Path::new("deep.sql"));
+        assert_eq!(e.location(), None);
+        assert_eq!(e.path(), Some(Path::new("deep.sql")));
+        assert_eq!(
+            e.to_string(),
+            "deep.sql: SQL parse error sql parser error: recursion limit exceeded"
+        );
+        assert!(matches!(e.source(), Some(inner) if inner.to_string().starts_with("SQL parse")));
+
+        let rewrapped = e.with_path(Path::new("other.sql"));
+        assert_eq!(rewrapped.path(), Some(Path::new("deep.sql")));
+    }
+
+    #[test]
+    fn test_doc_error_display_multiple_errors() {
+        use std::error::Error as _;
+
+        let e = DocError::MultipleErrors {
+            errors: vec![
+                DocError::TableNotFound { name: "a".to_owned() },
+                DocError::ColumnNotFound { name: "b".to_owned() },
+            ],
+        };
+        assert_eq!(
+            e.to_string(),
+            "2 error(s) found while building SqlDoc:\n\nTable not found in SqlDoc: a\n\nColumn not found in TableDoc: b"
+        );
+        assert!(e.source().is_none());
+        assert!(e.location().is_none());
+    }
 }
@@ -3,10 +3,196 @@
 //! This module finds file paths; it does not parse SQL or extract comments.
 
 use std::{
-    fs, io,
-    path::{Path, PathBuf},
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
 };
 
+use crate::encoding::{self, Encoding};
+
+/// A compiled glob pattern matched against `/`-separated paths.
+///
+/// Supported syntax:
+/// - `*` matches any run of characters within one path component
+/// - `?` matches a single character other than `/`
+/// - `**` matches any number of whole path components, including none
+/// - `[abc]`, `[a-z]` and `[!abc]` match one character from (or not from) a set
+///
+/// A pattern starting with `/` is anchored to the start of the path; any other
+/// pattern may match starting at any directory, so `warehouse/*.sql` matches
+/// both `warehouse/a.sql` and `repo/warehouse/a.sql`. Scan patterns given to
+/// [`ScanOptions`] are anchored whenever they contain a `/`, as in `.gitignore`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GlobPattern {
+    pattern: String,
+    tokens: Vec<GlobToken>,
+    anchored: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum GlobToken {
+    Literal(char),
+    AnyChar,
+    AnyRun,
+    AnyDirs,
+    AnyPath,
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+impl GlobPattern {
+    /// Compiles a [`GlobPattern`]. An unclosed `[` is matched literally.
+    ///
+    /// # Parameters
+    /// - `pattern`: the glob, using `/` as the path separator
+    #[must_use]
+    pub fn new(pattern: &str) -> Self {
+        Self::compile(pattern, pattern.starts_with('/'))
+    }
+
+    /// Compiles a pattern matched against paths relative to a root the way
+    /// `.gitignore` does: a pattern containing `/` is anchored to the root,
+    /// any other pattern matches a file or directory name at any depth
+    pub(crate) fn rooted(pattern: &str) -> Self {
+        Self::compile(pattern, pattern.contains('/'))
+    }
+
+    fn compile(pattern: &str, anchored: bool) -> Self {
+        let chars: Vec<char> = pattern.trim_start_matches('/').chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    let at_boundary = i == 0 || chars[i - 1] == '/';
+                    if at_boundary && chars.get(i + 2) == Some(&'/') {
+                        tokens.push(GlobToken::AnyDirs);
+                        i += 3;
+                    } else {
+                        tokens.push(GlobToken::AnyPath);
+                        i += 2;
+                    }
+                }
+                '*' => {
+                    tokens.push(GlobToken::AnyRun);
+                    i += 1;
+                }
+                '?' => {
+                    tokens.push(GlobToken::AnyChar);
+                    i += 1;
+                }
+                '[' => {
+                    if let Some((token, consumed)) = parse_class(&chars[i + 1..]) {
+                        tokens.push(token);
+                        i += consumed + 1;
+                    } else {
+                        tokens.push(GlobToken::Literal('['));
+                        i += 1;
+                    }
+                }
+                c => {
+                    tokens.push(GlobToken::Literal(c));
+                    i += 1;
+                }
+            }
+        }
+        Self { pattern: pattern.to_owned(), tokens, anchored }
+    }
+
+    /// Returns the pattern as it was written
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Checks whether `path` matches this pattern
+    ///
+    /// # Parameters
+    /// - `path`: the path to test; root and prefix components are ignored
+    #[must_use]
+    pub fn matches(&self, path: &Path) -> bool {
+        let text: Vec<char> = normalized_path(path).chars().collect();
+        if match_tokens(&self.tokens, &text) {
+            return true;
+        }
+        !self.anchored
+            && text
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| **c == '/')
+                .any(|(i, _)| match_tokens(&self.tokens, &text[i + 1..]))
+    }
+}
+
+/// Joins the normal components of `path` with `/`
+fn normalized_path(path: &Path) -> String {
+    let mut out = String::new();
+    for component in path.components() {
+        let part = match component {
+            Component::Normal(part) => part.to_string_lossy(),
+            Component::ParentDir => "..".into(),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => continue,
+        };
+        if !out.is_empty() {
+            out.push('/');
+        }
+        out.push_str(&part);
+    }
+    out
+}
+
+/// Parses the body of a `[...]` class, returning the token and the number of
+/// characters consumed including the closing `]`
+fn parse_class(chars: &[char]) -> Option<(GlobToken, usize)> {
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    let mut i = usize::from(negated);
+    let mut ranges = Vec::new();
+    while i < chars.len() {
+        let c = chars[i];
+        if c == ']' && !ranges.is_empty() {
+            return Some((GlobToken::Class { negated, ranges }, i + 1));
+        }
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|end| *end != ']') {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Backtracking matcher for the compiled glob tokens
+fn match_tokens(tokens: &[GlobToken], text: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+    match token {
+        GlobToken::Literal(c) => text.first() == Some(c) && match_tokens(rest, &text[1..]),
+        GlobToken::AnyChar => {
+            text.first().is_some_and(|c| *c != '/') && match_tokens(rest, &text[1..])
+        }
+        GlobToken::Class { negated, ranges } => text.first().is_some_and(|c| {
+            *c != '/'
+                && ranges.iter().any(|(lo, hi)| (lo..=hi).contains(&c)) != *negated
+                && match_tokens(rest, &text[1..])
+        }),
+        GlobToken::AnyRun => (0..=text.len())
+            .take_while(|i| *i == 0 || text[i - 1] != '/')
+            .any(|i| match_tokens(rest, &text[i..])),
+        GlobToken::AnyPath => (0..=text.len()).any(|i| match_tokens(rest, &text[i..])),
+        GlobToken::AnyDirs => {
+            match_tokens(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c == '/')
+                    .any(|(i, _)| match_tokens(rest, &text[i + 1..]))
+        }
+    }
+}
+
 /// A list of SQL files to exclude from processing.
 ///
 /// Entries in the deny list are treated as full [`PathBuf`] paths.
@@ -29,6 +215,366 @@ impl DenyList {
     }
 }
 
+/// Directory names skipped by default because they hold build output or
+/// vendored dependencies rather than the project's own SQL
+pub const DEFAULT_SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// File extensions scanned by default
+pub const DEFAULT_EXTENSIONS: &[&str] = &["sql"];
+
+/// Compression formats recognized by their file extension.
+///
+/// Compressed files are decompressed transparently when read, provided the
+/// crate feature for the format (`gzip` or `zstd`) is enabled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// Files ending in `.gz`
+    Gzip,
+    /// Files ending in `.zst`
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression implied by the last extension of `path`
+    ///
+    /// # Parameters
+    /// - `path`: the file path, e.g. `schema.sql.gz`
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if support for this format was compiled in
+    #[must_use]
+    pub const fn is_supported(self) -> bool {
+        match self {
+            Self::Gzip => cfg!(feature = "gzip"),
+            Self::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    /// Reads and decompresses the file at `path`
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the file cannot be read or decompressed,
+    ///   or of kind [`io::ErrorKind::Unsupported`] if the crate feature for
+    ///   the format is disabled
+    pub fn read(self, path: &Path) -> io::Result<Vec<u8>> {
+        if !self.is_supported() {
+            return Err(unsupported_compression(Some(path), self));
+        }
+        self.decompress_file(Some(path), &fs::read(path)?)
+    }
+
+    /// Decompresses `bytes` already read from a file or archive
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if `bytes` cannot be decompressed, or of kind
+    ///   [`io::ErrorKind::Unsupported`] if the crate feature for the format is
+    ///   disabled
+    pub fn decompress(self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        self.decompress_file(None, bytes)
+    }
+
+    /// Decompresses `bytes` read from `path`, naming it in errors
+    fn decompress_file(self, path: Option<&Path>, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip => gunzip(path, bytes),
+            Self::Zstd => unzstd(path, bytes),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gzip => write!(f, "gzip"),
+            Self::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+/// Decompresses `bytes` read from `path` if its extension names a
+/// [`Compression`] format, returning them unchanged otherwise
+///
+/// # Errors
+/// - Returns an [`io::Error`] if `bytes` cannot be decompressed, or of kind
+///   [`io::ErrorKind::Unsupported`] if the crate feature for the format is disabled
+pub(crate) fn decompress_for(path: &Path, bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    match Compression::from_path(path) {
+        Some(compression) => compression.decompress_file(Some(path), &bytes),
+        None => Ok(bytes),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gunzip(_path: Option<&Path>, bytes: &[u8]) -> io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+    flate2::read::MultiGzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(path: Option<&Path>, _bytes: &[u8]) -> io::Result<Vec<u8>> {
+    Err(unsupported_compression(path, Compression::Gzip))
+}
+
+#[cfg(feature = "zstd")]
+fn unzstd(_path: Option<&Path>, bytes: &[u8]) -> io::Result<Vec<u8>> {
+    zstd::stream::decode_all(bytes)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn unzstd(path: Option<&Path>, _bytes: &[u8]) -> io::Result<Vec<u8>> {
+    Err(unsupported_compression(path, Compression::Zstd))
+}
+
+/// The error returned when reading a compressed file, or bytes without a
+/// path, without the crate feature for its format
+fn unsupported_compression(path: Option<&Path>, compression: Compression) -> io::Error {
+    let input = path.map_or_else(|| "the input".to_owned(), |path| path.display().to_string());
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "{input} is {compression}-compressed; enable the `{compression}` feature to read it"
+        ),
+    )
+}
+
+/// How directory scans treat symbolic links
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SymlinkPolicy {
+    /// Follows links to files and directories; a directory already scanned
+    /// through another path is skipped, so link cycles are harmless, and a
+    /// file reached through several paths is only found once
+    #[default]
+    Follow,
+    /// Ignores symbolic links entirely
+    Skip,
+}
+
+/// Options controlling which files [`SqlFiles::with_options`] discovers under a directory.
+///
+/// Include and exclude patterns are [`GlobPattern`]s matched against each
+/// file's path relative to the scan root. As in `.gitignore`, a pattern
+/// containing `/` is anchored to the scan root, so `archive/**` only matches
+/// the top-level `archive` directory while `**/archive/**` matches any of
+/// them; a pattern without `/` matches a file name at any depth. A file is kept if it matches at
+/// least one include pattern (or none were given) and no exclude pattern.
+///
+/// By default `.gitignore` and `.ignore` files found under the scan root are
+/// honored, and hidden directories as well as [`DEFAULT_SKIPPED_DIRS`] are
+/// not descended into.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScanOptions {
+    include: Vec<GlobPattern>,
+    exclude: Vec<GlobPattern>,
+    ignore_files: bool,
+    hidden: bool,
+    skip_default_dirs: bool,
+    skip_dirs: Vec<String>,
+    max_depth: Option<usize>,
+    symlinks: SymlinkPolicy,
+    extensions: Option<Vec<String>>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanOptions {
+    /// Creates [`ScanOptions`] that discover every `.sql` file outside of
+    /// ignored, hidden and build directories
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            ignore_files: true,
+            hidden: false,
+            skip_default_dirs: true,
+            skip_dirs: Vec::new(),
+            max_depth: None,
+            symlinks: SymlinkPolicy::Follow,
+            extensions: None,
+        }
+    }
+
+    /// Sets whether `.gitignore` and `.ignore` files are honored
+    #[must_use]
+    pub const fn respect_ignore_files(mut self, yes: bool) -> Self {
+        self.ignore_files = yes;
+        self
+    }
+
+    /// Sets whether directories whose name starts with `.` are scanned
+    #[must_use]
+    pub const fn include_hidden(mut self, yes: bool) -> Self {
+        self.hidden = yes;
+        self
+    }
+
+    /// Sets whether the [`DEFAULT_SKIPPED_DIRS`] are skipped
+    #[must_use]
+    pub const fn skip_default_dirs(mut self, yes: bool) -> Self {
+        self.skip_default_dirs = yes;
+        self
+    }
+
+    /// Skips every directory called `name`, at any depth
+    ///
+    /// # Parameters
+    /// - `name`: the directory name, e.g. `"vendor"`
+    #[must_use]
+    pub fn skip_dir(mut self, name: &str) -> Self {
+        self.skip_dirs.push(name.to_owned());
+        self
+    }
+
+    /// Limits how deep the scan goes: `1` only finds files directly in the
+    /// scan root, `2` also those in its subdirectories, and so on
+    ///
+    /// # Parameters
+    /// - `depth`: the maximum depth
+    #[must_use]
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Sets how symbolic links are treated, see [`SymlinkPolicy`]
+    #[must_use]
+    pub const fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Replaces the [`DEFAULT_EXTENSIONS`] with the extensions to scan for.
+    ///
+    /// Compressed files such as `schema.sql.gz` are found as well when the
+    /// crate feature for their [`Compression`] is enabled.
+    ///
+    /// # Parameters
+    /// - `extensions`: extensions without the leading dot, e.g. `["sql", "ddl", "pgsql"]`
+    #[must_use]
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: &[S]) -> Self {
+        self.extensions = Some(extensions.iter().map(|ext| ext.as_ref().to_owned()).collect());
+        self
+    }
+
+    /// Checks whether `path` has one of the scanned extensions, optionally
+    /// followed by the extension of a supported [`Compression`]
+    ///
+    /// # Parameters
+    /// - `path`: the file path to check
+    #[must_use]
+    pub fn has_scanned_extension(&self, path: &Path) -> bool {
+        let path = match Compression::from_path(path) {
+            Some(compression) if !compression.is_supported() => return false,
+            Some(_) => path.file_stem().map_or(path, Path::new),
+            None => path,
+        };
+        let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+            return false;
+        };
+        self.extensions.as_ref().map_or_else(
+            || DEFAULT_EXTENSIONS.contains(&ext),
+            |extensions| extensions.iter().any(|scanned| scanned == ext),
+        )
+    }
+
+    /// Checks whether the scan should descend into `dir`, found at `depth`
+    fn descends_into(&self, dir: &Path, depth: usize) -> bool {
+        let name = dir.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        self.max_depth.is_none_or(|max| depth < max)
+            && (self.hidden || !name.starts_with('.'))
+            && !(self.skip_default_dirs && DEFAULT_SKIPPED_DIRS.contains(&name.as_ref()))
+            && !self.skip_dirs.iter().any(|skipped| *skipped == name)
+    }
+
+    /// Only discovers files matching `pattern`, or any other include pattern
+    ///
+    /// # Parameters
+    /// - `pattern`: a [`GlobPattern`] such as `"**/seed/*.sql"`
+    #[must_use]
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(GlobPattern::rooted(pattern));
+        self
+    }
+
+    /// Skips files matching `pattern`
+    ///
+    /// # Parameters
+    /// - `pattern`: a [`GlobPattern`] such as `"**/archive/**"`
+    #[must_use]
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(GlobPattern::rooted(pattern));
+        self
+    }
+
+    /// Adds a pattern written in `.gitignore` style: a leading `!` makes it an
+    /// exclude pattern, otherwise it is an include pattern
+    ///
+    /// # Parameters
+    /// - `pattern`: e.g. `"**/seed/*.sql"` or `"!**/archive/**"`
+    #[must_use]
+    pub fn pattern(self, pattern: &str) -> Self {
+        match pattern.strip_prefix('!') {
+            Some(exclude) => self.exclude(exclude),
+            None => self.include(pattern),
+        }
+    }
+
+    /// Returns the include patterns
+    #[must_use]
+    pub fn includes(&self) -> &[GlobPattern] {
+        &self.include
+    }
+
+    /// Returns the exclude patterns
+    #[must_use]
+    pub fn excludes(&self) -> &[GlobPattern] {
+        &self.exclude
+    }
+
+    /// Checks whether a file at `relative_path` under the scan root is kept
+    ///
+    /// # Parameters
+    /// - `relative_path`: the file's path relative to the scan root
+    #[must_use]
+    pub fn is_included(&self, relative_path: &Path) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(relative_path)))
+            && !self.exclude.iter().any(|glob| glob.matches(relative_path))
+    }
+
+    /// Checks whether a scan would find a file at `relative_path` under the
+    /// scan root: its extension is scanned, no directory on the way is
+    /// skipped or beyond the maximum depth, and the patterns keep it.
+    ///
+    /// Used for sources that are not directories on disk, such as
+    /// [`crate::provider::MemoryProvider`]; ignore files are not consulted.
+    ///
+    /// # Parameters
+    /// - `relative_path`: the file's path relative to the scan root
+    #[must_use]
+    pub fn is_scanned(&self, relative_path: &Path) -> bool {
+        let mut dirs: Vec<&Path> =
+            relative_path.ancestors().skip(1).filter(|dir| dir.file_name().is_some()).collect();
+        dirs.reverse();
+        self.has_scanned_extension(relative_path)
+            && dirs.iter().enumerate().all(|(depth, dir)| self.descends_into(dir, depth + 1))
+            && self.is_included(relative_path)
+    }
+}
+
 /// A collection of discovered `.sql` files under a given directory.
 #[derive(Debug)]
 pub struct SqlFiles {
@@ -40,7 +586,8 @@ impl SqlFiles {
     /// that are in the `deny_list`.
     ///
     /// Deny entries must match the discovered full path exactly (string equality on
-    /// the resulting [`PathBuf`]).
+    /// the resulting [`PathBuf`]). Directories are scanned with the default
+    /// [`ScanOptions`].
     ///
     /// # Parameters
     ///
@@ -52,10 +599,37 @@ impl SqlFiles {
     ///
     /// Returns an [`io::Error`] if directory traversal fails.
     pub fn new<P: AsRef<Path>>(path: P, deny_list: &[String]) -> io::Result<Self> {
-        let recursive_scan = recursive_dir_scan(path.as_ref())?;
+        Self::with_options(path, deny_list, &ScanOptions::default())
+    }
+
+    /// Same as [`SqlFiles::new`], but also drops the files filtered out by
+    /// `options`, whose include and exclude patterns are matched against the
+    /// path relative to `path`.
+    ///
+    /// # Parameters
+    ///
+    /// - `path`: any type that implements [`AsRef<Path>`].
+    /// - `deny_list`: optional list of path-like strings representing files to
+    ///   exclude.
+    /// - `options`: the [`ScanOptions`] to scan with
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`io::Error`] if directory traversal fails.
+    pub fn with_options<P: AsRef<Path>>(
+        path: P,
+        deny_list: &[String],
+        options: &ScanOptions,
+    ) -> io::Result<Self> {
+        let root = path.as_ref();
+        let recursive_scan = recursive_dir_scan(root, options)?;
         let mut allow_list: Vec<PathBuf> = {
             let deny = DenyList::new(deny_list);
-            recursive_scan.into_iter().filter(|p| !deny.deny_files().contains(p)).collect()
+            recursive_scan
+                .into_iter()
+                .filter(|p| !deny.deny_files().contains(p))
+                .filter(|p| options.is_included(p.strip_prefix(root).unwrap_or(p)))
+                .collect()
         };
         allow_list.sort();
         Ok(Self { sql_files: allow_list })
@@ -71,20 +645,153 @@ impl SqlFiles {
     }
 }
 
-/// Adds `.sql` files to a Vec of [`PathBuf`] recursively.
-fn recursive_dir_scan(path: &Path) -> io::Result<Vec<PathBuf>> {
+/// Adds `.sql` files to a Vec of [`PathBuf`] recursively, skipping the
+/// directories and ignored paths excluded by `options`.
+///
+/// The walk is iterative and remembers every directory it entered, so
+/// symlinks pointing back up the tree cannot make it loop. A file reached
+/// through several links is returned once, under its first path in sorted
+/// order. Every pending directory carries the ignore files of its ancestors,
+/// so the rules of a directory only apply below it.
+fn recursive_dir_scan(path: &Path, options: &ScanOptions) -> io::Result<Vec<PathBuf>> {
     let mut sql_files = Vec::new();
-    for entry in fs::read_dir(path)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("sql") {
-            sql_files.push(path);
-        } else if path.is_dir() {
-            let nested = recursive_dir_scan(&path)?;
-            sql_files.extend(nested);
+    let mut visited = HashSet::new();
+    visited.insert(file_id(path, &fs::metadata(path)?));
+    let mut pending: Vec<(PathBuf, usize, Vec<Rc<IgnoreFile>>)> =
+        vec![(path.to_path_buf(), 1, Vec::new())];
+    while let Some((dir, depth, mut ignore_files)) = pending.pop() {
+        if options.ignore_files {
+            for name in [".gitignore", ".ignore"] {
+                ignore_files.extend(IgnoreFile::load(&dir, name)?.map(Rc::new));
+            }
+        }
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            let metadata = if file_type.is_symlink() {
+                match options.symlinks {
+                    // Broken links are skipped rather than failing the scan
+                    SymlinkPolicy::Follow => match fs::metadata(&path) {
+                        Ok(metadata) => metadata,
+                        Err(_) => continue,
+                    },
+                    SymlinkPolicy::Skip => continue,
+                }
+            } else {
+                entry.metadata()?
+            };
+            if metadata.is_file() && options.has_scanned_extension(&path) {
+                if !is_ignored(&ignore_files, &path, false) {
+                    let id = file_id(&path, &metadata);
+                    sql_files.push((path, id));
+                }
+            } else if metadata.is_dir()
+                && options.descends_into(&path, depth)
+                && !is_ignored(&ignore_files, &path, true)
+                && visited.insert(file_id(&path, &metadata))
+            {
+                pending.push((path, depth + 1, ignore_files.clone()));
+            }
         }
     }
-    Ok(sql_files)
+    sql_files.sort();
+    let mut seen = HashSet::new();
+    Ok(sql_files.into_iter().filter_map(|(path, id)| seen.insert(id).then_some(path)).collect())
+}
+
+/// Identifies a file or directory independently of the path it was reached through
+#[cfg(unix)]
+type FileId = (u64, u64);
+/// Identifies a file or directory independently of the path it was reached through
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+/// Returns the device and inode of a file or directory
+#[cfg(unix)]
+fn file_id(_path: &Path, metadata: &fs::Metadata) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+/// Returns the canonical path of a file or directory, or `path` itself if it
+/// cannot be resolved
+#[cfg(not(unix))]
+fn file_id(path: &Path, _metadata: &fs::Metadata) -> FileId {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The rules of one `.gitignore` or `.ignore` file
+#[derive(Debug)]
+struct IgnoreFile {
+    /// The directory containing the file, which its rules are relative to
+    dir: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+/// A single line of an ignore file
+#[derive(Debug)]
+struct IgnoreRule {
+    glob: GlobPattern,
+    /// Set for `!pattern` lines, which re-include what earlier rules ignored
+    negated: bool,
+    /// Set for `pattern/` lines, which only match directories
+    dir_only: bool,
+}
+
+impl IgnoreFile {
+    /// Reads the ignore file `name` in `dir`, returning `None` if there is none
+    fn load(dir: &Path, name: &str) -> io::Result<Option<Self>> {
+        match fs::read_to_string(dir.join(name)) {
+            Ok(content) => Ok(Some(Self::parse(dir, &content))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parses the supported subset of the `.gitignore` format: comments,
+    /// `!` negation, trailing `/` for directories and anchoring on `/`
+    fn parse(dir: &Path, content: &str) -> Self {
+        let rules = content
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_end();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let negated = line.starts_with('!');
+                let line =
+                    line.strip_prefix('!').or_else(|| line.strip_prefix('\\')).unwrap_or(line);
+                let dir_only = line.ends_with('/');
+                let line = line.trim_end_matches('/');
+                if line.is_empty() {
+                    return None;
+                }
+                Some(IgnoreRule { glob: GlobPattern::rooted(line), negated, dir_only })
+            })
+            .collect();
+        Self { dir: dir.to_path_buf(), rules }
+    }
+}
+
+/// Checks `path` against the ignore files of its ancestors, the deepest file
+/// and the last matching rule taking precedence. Ignore files must be listed
+/// parents first.
+fn is_ignored(ignore_files: &[Rc<IgnoreFile>], path: &Path, is_dir: bool) -> bool {
+    for ignore_file in ignore_files.iter().rev() {
+        let Ok(relative) = path.strip_prefix(&ignore_file.dir) else {
+            continue;
+        };
+        let rule = ignore_file
+            .rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.matches(relative));
+        if let Some(rule) = rule {
+            return !rule.negated;
+        }
+    }
+    false
 }
 
 impl From<SqlFiles> for Vec<PathBuf> {
@@ -100,12 +807,33 @@ pub struct SqlContent {
 }
 
 impl SqlContent {
-    /// Creates structure from a [`PathBuf`]
+    /// Creates structure from a [`PathBuf`], decompressing the file first if
+    /// its extension names a [`Compression`] format
+    ///
+    /// The text encoding is detected from a byte order mark, defaulting to UTF-8.
     ///
     /// # Errors
     /// - Will return [`io::Error`] if the file is not able to be parsed
+    /// - Will return [`io::Error`] of kind [`io::ErrorKind::Unsupported`] for
+    ///   a compressed file whose [`Compression`] feature is disabled
     pub fn from_path(path: &Path) -> io::Result<Self> {
-        let content = fs::read_to_string(path)?;
+        Self::from_path_with_encoding(path, None)
+    }
+
+    /// Creates structure from a [`PathBuf`] like [`SqlContent::from_path`],
+    /// decoding the file with an explicit [`Encoding`] when one is given
+    ///
+    /// # Parameters
+    /// - `path`: the file to read
+    /// - `encoding`: the encoding of the file, or `None` to detect it
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlContent::from_path`]
+    /// - Will return [`io::Error`] of kind [`io::ErrorKind::InvalidData`] if
+    ///   the file is not valid in its encoding
+    pub fn from_path_with_encoding(path: &Path, encoding: Option<Encoding>) -> io::Result<Self> {
+        let bytes = decompress_for(path, fs::read(path)?)?;
+        let content = encoding::decode(&bytes, encoding)?;
         Ok(Self { path: path.to_path_buf(), content })
     }
 
@@ -180,7 +908,7 @@ mod tests {
         fs::File::create(&file2)?;
         fs::File::create(&non_sql1)?;
         fs::File::create(&non_sql2)?;
-        let mut found = recursive_dir_scan(base.as_path())?;
+        let mut found = recursive_dir_scan(base.as_path(), &ScanOptions::default())?;
         found.sort();
         let mut expected = vec![file1, file2];
         expected.sort();
@@ -206,7 +934,7 @@ mod tests {
         fs::File::create(&non_sql2)?;
 
         let bad_path = Path::new("bad_path");
-        let bad_dir_scan = recursive_dir_scan(bad_path);
+        let bad_dir_scan = recursive_dir_scan(bad_path, &ScanOptions::default());
         assert!(bad_dir_scan.is_err());
 
         let _ = fs::remove_dir_all(&base);
@@ -303,4 +1031,255 @@ mod tests {
         let _ = fs::remove_dir_all(&base);
         Ok(())
     }
+
+    #[test]
+    fn test_glob_pattern_matching() {
+        let cases = [
+            ("*.sql", "one.sql", true),
+            ("*.sql", "dir/one.sql", true),
+            ("/*.sql", "dir/one.sql", false),
+            ("/*.sql", "one.sql", true),
+            ("warehouse/**", "warehouse/a/b.sql", true),
+            ("warehouse/**", "repo/warehouse/b.sql", true),
+            ("/warehouse/**", "repo/warehouse/b.sql", false),
+            ("**/seed/*.sql", "seed/users.sql", true),
+            ("**/seed/*.sql", "db/seed/users.sql", true),
+            ("**/seed/*.sql", "db/seed/nested/users.sql", false),
+            ("a/**/b.sql", "a/b.sql", true),
+            ("a/**/b.sql", "a/x/y/b.sql", true),
+            ("v?.sql", "v1.sql", true),
+            ("v?.sql", "v10.sql", false),
+            ("v[0-9].sql", "v7.sql", true),
+            ("v[!0-9].sql", "v7.sql", false),
+            ("v[!0-9].sql", "vx.sql", true),
+            ("[unclosed.sql", "[unclosed.sql", true),
+        ];
+        for (pattern, path, expected) in cases {
+            assert_eq!(
+                GlobPattern::new(pattern).matches(Path::new(path)),
+                expected,
+                "pattern {pattern:?} against {path:?}"
+            );
+        }
+        assert!(GlobPattern::new("**/*.sql").matches(Path::new("/abs/dir/x.sql")));
+        assert_eq!(GlobPattern::new("**/*.sql").as_str(), "**/*.sql");
+    }
+
+    #[test]
+    fn test_scan_options_include_and_exclude_relative_to_root()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("scan_options_globs");
+        let _ = fs::remove_dir_all(&base);
+        for dir in ["db/seed", "db/archive/seed", "migrations"] {
+            fs::create_dir_all(base.join(dir))?;
+        }
+        for file in ["db/seed/users.sql", "db/archive/seed/old.sql", "migrations/001.sql"] {
+            fs::File::create(base.join(file))?;
+        }
+
+        let options = ScanOptions::new().pattern("**/seed/*.sql").pattern("!**/archive/**");
+        assert_eq!(options.includes().len(), 1);
+        assert_eq!(options.excludes().len(), 1);
+        let found = SqlFiles::with_options(&base, &[], &options)?.sql_files();
+        assert_eq!(found, vec![base.join("db/seed/users.sql")]);
+
+        let options = ScanOptions::new().exclude("/db/**");
+        let found = SqlFiles::with_options(&base, &[], &options)?.sql_files();
+        assert_eq!(found, vec![base.join("migrations/001.sql")]);
+
+        let options = ScanOptions::new().exclude("archive/**");
+        assert_eq!(SqlFiles::with_options(&base, &[], &options)?.sql_files().len(), 3);
+        let options = ScanOptions::new().exclude("db/archive/**").include("users.sql");
+        let found = SqlFiles::with_options(&base, &[], &options)?.sql_files();
+        assert_eq!(found, vec![base.join("db/seed/users.sql")]);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_skips_ignored_hidden_and_build_dirs() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("scan_options_ignore_files");
+        let _ = fs::remove_dir_all(&base);
+        for dir in [".git", "target", "node_modules/pkg", "generated", "schema/tmp", "vendor"] {
+            fs::create_dir_all(base.join(dir))?;
+        }
+        for file in [
+            ".git/hook.sql",
+            "target/fixture.sql",
+            "node_modules/pkg/fixture.sql",
+            "generated/out.sql",
+            "schema/users.sql",
+            "schema/scratch.sql",
+            "schema/keep.sql",
+            "schema/tmp/t.sql",
+            "vendor/lib.sql",
+        ] {
+            fs::File::create(base.join(file))?;
+        }
+        fs::write(base.join(".gitignore"), "# build output\ngenerated/\n/schema/tmp\n")?;
+        fs::write(base.join("schema/.ignore"), "*.sql\n!keep.sql\n!users.sql\n")?;
+
+        let found = SqlFiles::new(&base, &[])?.sql_files();
+        assert_eq!(
+            found,
+            vec![
+                base.join("schema/keep.sql"),
+                base.join("schema/users.sql"),
+                base.join("vendor/lib.sql")
+            ]
+        );
+
+        let options = ScanOptions::new().skip_dir("vendor").max_depth(1);
+        assert!(SqlFiles::with_options(&base, &[], &options)?.sql_files().is_empty());
+
+        let everything = ScanOptions::new()
+            .respect_ignore_files(false)
+            .include_hidden(true)
+            .skip_default_dirs(false);
+        assert_eq!(SqlFiles::with_options(&base, &[], &everything)?.sql_files().len(), 9);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_applies_ignore_files_below_their_directory_only()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("scan_ignore_files_scope");
+        let _ = fs::remove_dir_all(&base);
+        for dir in ["a", "b", "c"] {
+            fs::create_dir_all(base.join(dir))?;
+            fs::File::create(base.join(dir).join("generated.sql"))?;
+            fs::File::create(base.join(dir).join("schema.sql"))?;
+        }
+        fs::write(base.join(".gitignore"), "generated.sql\n")?;
+        fs::write(base.join("a/.gitignore"), "!generated.sql\n")?;
+        fs::write(base.join("b/.ignore"), "schema.sql\n")?;
+
+        let found = SqlFiles::new(&base, &[])?.sql_files();
+        assert_eq!(
+            found,
+            vec![
+                base.join("a/generated.sql"),
+                base.join("a/schema.sql"),
+                base.join("c/schema.sql")
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_symlink_policy_and_cycles() -> Result<(), Box<dyn std::error::Error>> {
+        use std::os::unix::fs::symlink;
+
+        let base = env::temp_dir().join("scan_symlink_cycles");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("schema/nested"))?;
+        fs::create_dir_all(base.join("shared"))?;
+        fs::File::create(base.join("schema/nested/users.sql"))?;
+        fs::File::create(base.join("shared/common.sql"))?;
+        symlink(&base, base.join("schema/nested/loop"))?;
+        symlink(base.join("shared"), base.join("schema/shared"))?;
+        symlink(base.join("shared/common.sql"), base.join("schema/common.sql"))?;
+        symlink(base.join("missing.sql"), base.join("schema/broken.sql"))?;
+
+        let followed = SqlFiles::new(&base, &[])?.sql_files();
+        assert!(followed.contains(&base.join("schema/nested/users.sql")));
+        assert!(followed.contains(&base.join("schema/common.sql")));
+        assert_eq!(followed.iter().filter(|p| p.ends_with("common.sql")).count(), 1);
+        assert_eq!(followed.len(), 2);
+
+        let options = ScanOptions::new().symlinks(SymlinkPolicy::Skip);
+        let skipped = SqlFiles::with_options(&base, &[], &options)?.sql_files();
+        assert_eq!(
+            skipped,
+            vec![base.join("schema/nested/users.sql"), base.join("shared/common.sql")]
+        );
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_configurable_extensions() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("scan_configurable_extensions");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        for file in ["a.sql", "b.ddl", "c.pgsql", "d.psql", "e.txt", "f.sql.bak"] {
+            fs::File::create(base.join(file))?;
+        }
+
+        let found = SqlFiles::new(&base, &[])?.sql_files();
+        assert_eq!(found, vec![base.join("a.sql")]);
+
+        let options = ScanOptions::new().extensions(&["ddl", "pgsql", "psql"]);
+        let found = SqlFiles::with_options(&base, &[], &options)?.sql_files();
+        assert_eq!(found, vec![base.join("b.ddl"), base.join("c.pgsql"), base.join("d.psql")]);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_extensions_follow_enabled_features() {
+        let options = ScanOptions::new();
+        assert_eq!(Compression::from_path(Path::new("a.sql.gz")), Some(Compression::Gzip));
+        assert_eq!(Compression::from_path(Path::new("a.sql.zst")), Some(Compression::Zstd));
+        assert_eq!(Compression::from_path(Path::new("a.sql")), None);
+        assert_eq!(options.has_scanned_extension(Path::new("a.sql.gz")), cfg!(feature = "gzip"));
+        assert_eq!(options.has_scanned_extension(Path::new("a.sql.zst")), cfg!(feature = "zstd"));
+        assert!(!options.has_scanned_extension(Path::new("a.txt.gz")));
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn test_reading_gzip_without_feature_is_unsupported() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let base = env::temp_dir().join("read_gzip_without_feature");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        let file = base.join("schema.sql.gz");
+        fs::write(&file, [0x1f, 0x8b])?;
+        let err = SqlContent::from_path(&file).err().unwrap_or_else(|| panic!("expected error"));
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(err.to_string().contains("enable the `gzip` feature"));
+        assert!(err.to_string().starts_with(&file.display().to_string()));
+        let err = decompress_for(Path::new("dump/schema.sql.gz"), vec![0x1f, 0x8b])
+            .err()
+            .unwrap_or_else(|| panic!("expected error"));
+        assert!(err.to_string().starts_with("dump/schema.sql.gz is gzip-compressed"));
+        let err =
+            Compression::Gzip.decompress(&[]).err().unwrap_or_else(|| panic!("expected error"));
+        assert!(err.to_string().starts_with("the input is gzip-compressed"));
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_compressed_files_are_decompressed() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        let base = env::temp_dir().join("read_compressed_sql_files");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        let sql = "CREATE TABLE users (id INTEGER);";
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(sql.as_bytes())?;
+        fs::write(base.join("a.sql.gz"), gzip.finish()?)?;
+        fs::write(base.join("b.sql.zst"), zstd::stream::encode_all(sql.as_bytes(), 0)?)?;
+
+        let files = SqlFiles::new(&base, &[])?.sql_files();
+        assert_eq!(files, vec![base.join("a.sql.gz"), base.join("b.sql.zst")]);
+        for file in files {
+            assert_eq!(SqlContent::from_path(&file)?.content(), sql);
+        }
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
 }
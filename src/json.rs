@@ -0,0 +1,578 @@
+//! Export a [`SqlDoc`] as a stable, versioned JSON document and import it back.
+//!
+//! Unlike the raw `serde` representation, this format is documented by
+//! [`JSON_SCHEMA`] and only changes together with [`JSON_FORMAT_VERSION`]:
+//!
+//! ```json
+//! {
+//!   "format_version": 3,
+//!   "tables": [
+//!     {
+//!       "schema": null,
+//!       "name": "users",
+//!       "doc": "Registered accounts",
+//!       "path": "sql/users.sql",
+//!       "line": 2,
+//!       "columns": [
+//!         {
+//!           "name": "id",
+//!           "doc": null,
+//!           "ordinal": 1,
+//!           "data_type": "INTEGER",
+//!           "nullable": false,
+//!           "default": null,
+//!           "primary_key": true,
+//!           "foreign_key": null
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Output is deterministic: tables are ordered by schema, name and path and
+//! columns by their position in the table, and absent values are written as explicit `null`s rather than omitted.
+//! Documents of older format versions can still be imported.
+//!
+//! Format versions:
+//! - 1: table and column names, docs and paths
+//! - 2: adds each table's `line` and each column's `ordinal`, `data_type`,
+//!   `nullable`, `default`, `primary_key` and `foreign_key`
+//! - 3: drops the top-level `source_dialects`
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    docs::{ColumnDoc, ForeignKey, TableDoc},
+    error::DocError,
+    render::{Renderer, Sink},
+    sql_doc::SqlDoc,
+};
+
+/// The version written to `format_version`, increased whenever the format
+/// changes; documents with a newer version are rejected on import
+pub const JSON_FORMAT_VERSION: u32 = 3;
+
+/// The file [`Json`] writes the document to through [`Renderer`]
+pub const OUTPUT_FILE: &str = "sql_docs.json";
+
+/// The JSON Schema (draft 2020-12) describing documents written by [`export`]
+pub const JSON_SCHEMA: &str = include_str!("../schema/sql_docs.schema.json");
+
+#[derive(Serialize, Deserialize)]
+struct JsonDocument {
+    format_version: u32,
+    tables: Vec<JsonTable>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonTable {
+    schema: Option<String>,
+    name: String,
+    doc: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    line: Option<u64>,
+    columns: Vec<JsonColumn>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonColumn {
+    name: String,
+    doc: Option<String>,
+    // the fields below were added in format version 2
+    #[serde(default)]
+    ordinal: usize,
+    #[serde(default)]
+    data_type: Option<String>,
+    #[serde(default = "nullable_by_default")]
+    nullable: bool,
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    primary_key: bool,
+    #[serde(default)]
+    foreign_key: Option<JsonForeignKey>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonForeignKey {
+    schema: Option<String>,
+    table: String,
+    column: Option<String>,
+}
+
+impl From<&SqlDoc> for JsonDocument {
+    fn from(doc: &SqlDoc) -> Self {
+        let mut tables: Vec<JsonTable> = doc.tables().iter().map(JsonTable::from).collect();
+        tables.sort_by(|a, b| (&a.schema, &a.name, &a.path).cmp(&(&b.schema, &b.name, &b.path)));
+        Self { format_version: JSON_FORMAT_VERSION, tables }
+    }
+}
+
+impl From<&TableDoc> for JsonTable {
+    fn from(table: &TableDoc) -> Self {
+        let columns = table
+            .columns_in_order()
+            .into_iter()
+            .map(|column| JsonColumn {
+                name: column.name().to_owned(),
+                doc: column.doc().map(str::to_owned),
+                ordinal: column.ordinal(),
+                data_type: column.data_type().map(str::to_owned),
+                nullable: column.is_nullable(),
+                default: column.default().map(str::to_owned),
+                primary_key: column.is_primary_key(),
+                foreign_key: column.foreign_key().map(|key| JsonForeignKey {
+                    schema: key.schema().map(str::to_owned),
+                    table: key.table().to_owned(),
+                    column: key.column().map(str::to_owned),
+                }),
+            })
+            .collect();
+        Self {
+            schema: table.schema().map(str::to_owned),
+            name: table.name().to_owned(),
+            doc: table.doc().map(str::to_owned),
+            path: table.path().map(path_string),
+            line: table.line(),
+            columns,
+        }
+    }
+}
+
+impl From<JsonColumn> for ColumnDoc {
+    fn from(column: JsonColumn) -> Self {
+        let mut doc = Self::new(column.name, column.doc)
+            .with_nullable(column.nullable)
+            .with_primary_key(column.primary_key);
+        if let Some(data_type) = column.data_type {
+            doc = doc.with_data_type(data_type);
+        }
+        if let Some(default) = column.default {
+            doc = doc.with_default(default);
+        }
+        if let Some(key) = column.foreign_key {
+            doc = doc.with_foreign_key(ForeignKey::new(key.schema, key.table, key.column));
+        }
+        doc
+    }
+}
+
+impl From<JsonTable> for TableDoc {
+    fn from(mut table: JsonTable) -> Self {
+        // version 1 documents have no ordinals and keep their order
+        table.columns.sort_by_key(|column| column.ordinal);
+        let columns = table.columns.into_iter().map(ColumnDoc::from).collect();
+        let doc =
+            Self::new(table.schema, table.name, table.doc, columns, table.path.map(PathBuf::from));
+        match table.line {
+            Some(line) => doc.with_line(line),
+            None => doc,
+        }
+    }
+}
+
+/// Default for columns of version 1 documents, which have no `nullable` field
+const fn nullable_by_default() -> bool {
+    true
+}
+
+/// Writes paths with `/` separators so documents do not depend on the platform
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Converts an error raised while parsing a document
+fn import_error(error: &serde_json::Error) -> DocError {
+    let message = error.to_string();
+    // serde_json appends the position to its messages; it is kept in the fields instead
+    let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(text, _)| text);
+    DocError::InvalidJson {
+        message: message.to_owned(),
+        line: error.line() as u64,
+        column: error.column() as u64,
+    }
+}
+
+/// Exports `doc` as a pretty-printed JSON document
+///
+/// # Parameters
+/// - `doc`: the [`SqlDoc`] to export
+///
+/// # Errors
+/// - Returns [`DocError::InvalidJson`] if the document cannot be serialized
+pub fn export(doc: &SqlDoc) -> Result<String, DocError> {
+    serde_json::to_string_pretty(&JsonDocument::from(doc)).map_err(|e| DocError::InvalidJson {
+        message: e.to_string(),
+        line: 0,
+        column: 0,
+    })
+}
+
+/// Exports `doc` as a pretty-printed JSON document into `writer`
+///
+/// # Parameters
+/// - `doc`: the [`SqlDoc`] to export
+/// - `writer`: where to write the document, e.g. a file
+///
+/// # Errors
+/// - Returns [`DocError::FileReadError`] if writing fails
+pub fn export_to<W: io::Write>(doc: &SqlDoc, mut writer: W) -> Result<(), DocError> {
+    writer.write_all(export(doc)?.as_bytes())?;
+    Ok(())
+}
+
+/// Imports a [`SqlDoc`] from a JSON document written by [`export`]
+///
+/// # Parameters
+/// - `json`: the document
+///
+/// # Errors
+/// - Returns [`DocError::InvalidJson`] if the document is malformed, does not
+///   match [`JSON_SCHEMA`] or has a `format_version` newer than [`JSON_FORMAT_VERSION`]
+pub fn import(json: &str) -> Result<SqlDoc, DocError> {
+    let document: JsonDocument = serde_json::from_str(json).map_err(|e| import_error(&e))?;
+    if document.format_version > JSON_FORMAT_VERSION {
+        return Err(DocError::InvalidJson {
+            message: format!(
+                "format version {} is newer than the supported version {JSON_FORMAT_VERSION}",
+                document.format_version
+            ),
+            line: 0,
+            column: 0,
+        });
+    }
+    let tables = document.tables.into_iter().map(TableDoc::from).collect();
+    Ok(SqlDoc::new(tables))
+}
+
+/// Imports a [`SqlDoc`] from a JSON document read from `reader`
+///
+/// # Parameters
+/// - `reader`: where to read the document from, e.g. a file
+///
+/// # Errors
+/// - Returns [`DocError::FileReadError`] if reading fails
+/// - Returns the same errors as [`import`]
+pub fn import_from<R: io::Read>(mut reader: R) -> Result<SqlDoc, DocError> {
+    let mut json = String::new();
+    reader.read_to_string(&mut json)?;
+    import(&json)
+}
+
+/// Writes the document returned by [`export`] to [`OUTPUT_FILE`], so the JSON
+/// format can be selected through a [`crate::render::Registry`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Json;
+
+impl Renderer for Json {
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        sink.file(OUTPUT_FILE).push_str(&export(doc)?);
+        Ok(())
+    }
+}
+
+impl SqlDoc {
+    /// Exports this [`SqlDoc`] as a JSON document, see [`export`]
+    ///
+    /// # Errors
+    /// - Returns the same errors as [`export`]
+    pub fn to_json(&self) -> Result<String, DocError> {
+        export(self)
+    }
+
+    /// Imports a [`SqlDoc`] from a JSON document, see [`import`]
+    ///
+    /// # Errors
+    /// - Returns the same errors as [`import`]
+    ///
+    /// # Examples
+    /// ```
+    /// use sql_docs::{SqlDoc, GenericDialect};
+    ///
+    /// let doc = SqlDoc::builder_from_str("-- Accounts\nCREATE TABLE users (id INTEGER);")
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// let json = doc.to_json().unwrap();
+    /// assert_eq!(SqlDoc::from_json(&json).unwrap(), doc);
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, DocError> {
+        import(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::Value;
+    use sqlparser::dialect::GenericDialect;
+
+    use crate::{
+        DocError, SqlDoc,
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        json::{JSON_FORMAT_VERSION, JSON_SCHEMA, export, import, import_from},
+    };
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Accounts".to_owned()),
+            vec![
+                ColumnDoc::new("name".to_owned(), None).with_data_type("TEXT").with_foreign_key(
+                    ForeignKey::new(None, "people".to_owned(), Some("name".to_owned())),
+                ),
+                ColumnDoc::new("id".to_owned(), Some("Key".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+            ],
+            Some(PathBuf::from("sql/users.sql")),
+        )
+        .with_line(3);
+        let legacy = TableDoc::new(None, "users".to_owned(), None, vec![], None);
+        SqlDoc::new(vec![users, legacy])
+    }
+
+    /// The exported table `name` in `schema`, or `null` if there is none
+    fn table<'a>(json: &'a Value, schema: Option<&str>, name: &str) -> &'a Value {
+        json["tables"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|table| table["schema"].as_str() == schema && table["name"] == name)
+            .unwrap_or(&Value::Null)
+    }
+
+    #[test]
+    fn test_export_is_deterministic_with_explicit_nulls() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let json: Value = serde_json::from_str(&export(&sample())?)?;
+        assert_eq!(json["format_version"], JSON_FORMAT_VERSION);
+        let legacy = table(&json, None, "users");
+        assert_eq!(legacy["path"], Value::Null);
+        assert_eq!(legacy["line"], Value::Null);
+        assert_eq!(json["tables"][0], *legacy);
+        let users = table(&json, Some("app"), "users");
+        assert_eq!(users["path"], "sql/users.sql");
+        assert_eq!(users["line"], 3);
+        let columns = &users["columns"];
+        assert_eq!(columns[0]["name"], "name");
+        assert_eq!(columns[0]["doc"], Value::Null);
+        assert_eq!(columns[0]["nullable"], true);
+        assert_eq!(columns[0]["foreign_key"]["table"], "people");
+        assert_eq!(columns[1]["ordinal"], 2);
+        assert_eq!(columns[1]["doc"], "Key");
+        assert_eq!(columns[1]["nullable"], false);
+        assert_eq!(columns[1]["primary_key"], true);
+        assert_eq!(columns[1]["foreign_key"], Value::Null);
+        assert_eq!(export(&sample())?, export(&SqlDoc::new(sample().into_tables()))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_round_trips_and_rejects_bad_documents() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let doc = sample();
+        let imported = import(&export(&doc)?)?;
+        assert_eq!(imported.number_of_tables(), doc.number_of_tables());
+        assert_eq!(export(&imported)?, export(&doc)?);
+        assert_eq!(export(&import_from(export(&doc)?.as_bytes())?)?, export(&doc)?);
+
+        let newer = r#"{"format_version": 4, "tables": []}"#;
+        assert!(matches!(import(newer), Err(DocError::InvalidJson { line: 0, .. })));
+        let malformed = "{\n  \"format_version\": 1,\n  \"tables\": [{}]\n}";
+        let err = import(malformed).err().unwrap_or_else(|| panic!("expected an error"));
+        assert!(matches!(err, DocError::InvalidJson { line: 3, .. }));
+        assert!(err.to_string().starts_with("invalid JSON document at line 3"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_accepts_version_1_documents() -> Result<(), Box<dyn std::error::Error>> {
+        let json = r#"{
+            "format_version": 1,
+            "tables": [{
+                "schema": null, "name": "users", "doc": null, "path": null,
+                "columns": [{ "name": "id", "doc": "Key" }]
+            }],
+            "source_dialects": []
+        }"#;
+        let doc = import(json)?;
+        let column = doc.table("users", None)?.column("id")?;
+        assert_eq!(column.doc(), Some("Key"));
+        assert_eq!(column.data_type(), None);
+        assert!(column.is_nullable());
+        assert_eq!(column.ordinal(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_accepts_version_2_documents() -> Result<(), Box<dyn std::error::Error>> {
+        let json = r#"{
+            "format_version": 2,
+            "tables": [{
+                "schema": null, "name": "orders", "doc": null, "path": "orders.sql", "line": 4,
+                "columns": [
+                    {
+                        "name": "user_id", "doc": "Buyer", "ordinal": 2, "data_type": "INTEGER",
+                        "nullable": false, "default": null, "primary_key": false,
+                        "foreign_key": { "schema": null, "table": "users", "column": "id" }
+                    },
+                    {
+                        "name": "id", "doc": null, "ordinal": 1, "data_type": "INTEGER",
+                        "nullable": false, "default": null, "primary_key": true,
+                        "foreign_key": null
+                    }
+                ]
+            }],
+            "source_dialects": [{ "path": "orders.sql", "dialect": "postgres", "detected": true }]
+        }"#;
+        let doc = import(json)?;
+        let table = doc.table("orders", None)?;
+        assert_eq!(table.line(), Some(4));
+        let user_id = table.column("user_id")?;
+        assert_eq!(user_id.ordinal(), 2);
+        assert!(!user_id.is_nullable());
+        assert_eq!(user_id.foreign_key().map(ForeignKey::table), Some("users"));
+        assert!(table.column("id")?.is_primary_key());
+        assert_eq!(import(&export(&doc)?)?, doc);
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_describes_exported_keys() -> Result<(), Box<dyn std::error::Error>> {
+        let schema: Value = serde_json::from_str(JSON_SCHEMA)?;
+        let exported: Value = serde_json::from_str(&export(&sample())?)?;
+        let keys = |value: &Value| -> Vec<String> {
+            let mut keys: Vec<String> =
+                value.as_object().map(|o| o.keys().cloned().collect()).unwrap_or_default();
+            keys.sort();
+            keys
+        };
+        let mut document_keys = keys(&schema["properties"]);
+        // only written by format versions 1 and 2
+        document_keys.retain(|key| key != "source_dialects");
+        assert_eq!(document_keys, keys(&exported));
+        let users = table(&exported, Some("app"), "users");
+        assert_eq!(keys(&schema["$defs"]["table"]["properties"]), keys(users));
+        assert_eq!(keys(&schema["$defs"]["column"]["properties"]), keys(&users["columns"][0]));
+        assert_eq!(
+            keys(&schema["$defs"]["foreign_key"]["properties"]),
+            keys(&users["columns"][0]["foreign_key"])
+        );
+        assert_eq!(schema["properties"]["format_version"]["maximum"], JSON_FORMAT_VERSION);
+        Ok(())
+    }
+
+    /// Validates `value` against the subset of JSON Schema used by
+    /// [`JSON_SCHEMA`], returning the paths of the values that do not match
+    fn schema_errors(root: &Value, schema: &Value, value: &Value, at: &str) -> Vec<String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/$defs/");
+            return schema_errors(root, &root["$defs"][name], value, at);
+        }
+        if let Some(options) = schema["oneOf"].as_array() {
+            let matching =
+                options.iter().filter(|s| schema_errors(root, s, value, at).is_empty()).count();
+            return if matching == 1 { vec![] } else { vec![format!("{at}: oneOf")] };
+        }
+        let type_matches = |name: &Value| match name.as_str() {
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_u64() || value.is_i64(),
+            Some("boolean") => value.is_boolean(),
+            Some("null") => value.is_null(),
+            _ => false,
+        };
+        let types =
+            schema["type"].as_array().cloned().unwrap_or_else(|| vec![schema["type"].clone()]);
+        if !schema["type"].is_null() && !types.iter().any(type_matches) {
+            return vec![format!("{at}: expected {}", schema["type"])];
+        }
+        let mut errors = Vec::new();
+        let number = value.as_f64();
+        if let Some(minimum) = schema["minimum"].as_f64().filter(|m| number.is_some_and(|n| n < *m))
+        {
+            errors.push(format!("{at}: below {minimum}"));
+        }
+        if let Some(maximum) = schema["maximum"].as_f64().filter(|m| number.is_some_and(|n| n > *m))
+        {
+            errors.push(format!("{at}: above {maximum}"));
+        }
+        for required in schema["required"].as_array().into_iter().flatten() {
+            if value.get(required.as_str().unwrap_or_default()).is_none() {
+                errors.push(format!("{at}: missing {required}"));
+            }
+        }
+        if let (Some(properties), Some(object)) =
+            (schema["properties"].as_object(), value.as_object())
+        {
+            for (key, property) in object {
+                match properties.get(key) {
+                    Some(schema) => {
+                        errors.extend(schema_errors(
+                            root,
+                            schema,
+                            property,
+                            &format!("{at}.{key}"),
+                        ));
+                    }
+                    None => errors.push(format!("{at}: unknown {key}")),
+                }
+            }
+        }
+        for (index, item) in value.as_array().into_iter().flatten().enumerate() {
+            errors.extend(schema_errors(root, &schema["items"], item, &format!("{at}[{index}]")));
+        }
+        errors
+    }
+
+    #[test]
+    fn test_schema_validates_exported_and_version_1_documents()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let schema: Value = serde_json::from_str(JSON_SCHEMA)?;
+        let validate = |value: &Value| schema_errors(&schema, &schema, value, "$");
+
+        let defaulted = SqlDoc::builder_from_str("CREATE TABLE t (id INTEGER NOT NULL DEFAULT 0);")
+            .build::<GenericDialect>()?;
+        for doc in [sample(), defaulted] {
+            let exported: Value = serde_json::from_str(&export(&doc)?)?;
+            assert_eq!(validate(&exported), Vec::<String>::new());
+        }
+        let version_1: Value = serde_json::from_str(
+            r#"{
+                "format_version": 1,
+                "tables": [{
+                    "schema": null, "name": "users", "doc": null, "path": null,
+                    "columns": [{ "name": "id", "doc": "Key" }]
+                }],
+                "source_dialects": []
+            }"#,
+        )?;
+        assert_eq!(validate(&version_1), Vec::<String>::new());
+
+        let mut invalid: Value = serde_json::from_str(&export(&sample())?)?;
+        invalid["format_version"] = (JSON_FORMAT_VERSION + 1).into();
+        invalid["tables"][1]["columns"][0]["nullable"] = "yes".into();
+        invalid["tables"][1]["columns"][0]["ordinal"] = 0.into();
+        invalid["tables"][1]["columns"][0]["foreign_key"]["table"] = Value::Null;
+        assert_eq!(
+            validate(&invalid),
+            [
+                "$.format_version: above 3",
+                "$.tables[1].columns[0].foreign_key: oneOf",
+                "$.tables[1].columns[0].nullable: expected \"boolean\"",
+                "$.tables[1].columns[0].ordinal: below 1",
+            ]
+        );
+        Ok(())
+    }
+}
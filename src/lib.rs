@@ -8,22 +8,38 @@
 //! - [`files`]    — Discover and load `.sql` files from disk
 //! - [`ast`]      — Parse SQL into an AST using [`sqlparser`]
 //! - [`comments`] — Extract and model SQL comments and spans
+//! - [`config`]   — Owned, reusable build settings ([`SqlDocConfig`])
 //! - [`docs`]     — Generate structured documentation (`TableDoc`, `ColumnDoc`)
+//! - [`provider`] — Read sources from disk, memory or archives through [`provider::SourceProvider`]
+//! - [`encoding`] — Decode UTF-8, UTF-16 and Latin-1 sources, stripping byte order marks
+//! - `json`       — Stable, versioned JSON export and import (`json` feature)
+//! - [`render`]   — Render documentation as Markdown, HTML, diagrams and more, selected by format name
 //! - [`sql_doc`]  — Build the top-level [`SqlDoc`] and primary entry point
+//! - [`warnings`] — Non-fatal diagnostics for orphaned or misattached comments
 //!
 //! **Start here:** [`SqlDoc::from_dir`], [`SqlDoc::from_path`], or [`SqlDoc::builder_from_str`]
 
 pub mod ast;
 pub mod comments;
+pub mod config;
 pub mod docs;
+pub mod encoding;
 pub mod error;
 pub mod files;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod provider;
+pub mod render;
 pub mod source;
 pub mod sql_doc;
+pub mod warnings;
 pub use crate::comments::{LeadingCommentCapture, MultiFlatten};
-pub use crate::docs::{ColumnDoc, TableDoc};
+pub use crate::config::SqlDocConfig;
+pub use crate::docs::{ColumnDoc, ForeignKey, TableDoc};
+pub use crate::encoding::Encoding;
 pub use crate::error::DocError;
 pub use crate::sql_doc::{SqlDoc, SqlDocBuilder};
+pub use crate::warnings::DocWarning;
 
 /// Common imports for typical usage of this crate.
 pub mod prelude {
@@ -33,12 +49,13 @@ pub mod prelude {
         RedshiftSqlDialect, SQLiteDialect, SnowflakeDialect,
     };
     pub use crate::{
-        ColumnDoc, DocError, LeadingCommentCapture, MultiFlatten, SqlDoc, SqlDocBuilder, TableDoc,
+        ColumnDoc, DocError, DocWarning, LeadingCommentCapture, MultiFlatten, SqlDoc,
+        SqlDocBuilder, SqlDocConfig, TableDoc,
     };
 }
 
 pub use sqlparser::dialect::{
     AnsiDialect, BigQueryDialect, ClickHouseDialect, DatabricksDialect, Dialect, DuckDbDialect,
     GenericDialect, HiveDialect, MsSqlDialect, MySqlDialect, OracleDialect, PostgreSqlDialect,
-    RedshiftSqlDialect, SQLiteDialect, SnowflakeDialect,
+    RedshiftSqlDialect, SQLiteDialect, SnowflakeDialect, dialect_from_str,
 };
@@ -0,0 +1,432 @@
+//! Pluggable sources of SQL files.
+//!
+//! A [`SourceProvider`] lists and reads the files a build documents, so that
+//! [`SqlDoc::from_provider`](crate::SqlDoc::from_provider) can work on
+//! something other than a directory on disk: files held in memory, the
+//! contents of a tar or zip archive, or a virtual file system of your own.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+use crate::files::{Compression, decompress_for};
+use crate::files::{ScanOptions, SqlFiles};
+
+/// A source of SQL files, such as a directory, an archive or an in-memory map.
+///
+/// Implementations list the files a scan would find and read their raw bytes.
+/// Decompressing `.gz`/`.zst` files and decoding text is done by the caller,
+/// so `read` should return the bytes exactly as stored.
+pub trait SourceProvider: fmt::Debug {
+    /// Lists the paths of the files to document, in a stable order
+    ///
+    /// # Parameters
+    /// - `options`: the extensions, patterns and directory rules to apply
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the files cannot be listed
+    fn list(&self, options: &ScanOptions) -> io::Result<Vec<PathBuf>>;
+
+    /// Reads the raw bytes of the file at `path`, one of the listed paths
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the file cannot be read
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Returns the root the listed paths are under, if any, so that include
+    /// patterns and dialect overrides can be matched against relative paths
+    fn root(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// A [`SourceProvider`] scanning a directory on disk, as used by
+/// [`SqlDoc::from_dir`](crate::SqlDoc::from_dir).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FsProvider {
+    root: PathBuf,
+}
+
+impl FsProvider {
+    /// Creates a provider for the files under `root`
+    ///
+    /// # Parameters
+    /// - `root`: the directory to scan
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self { root: root.as_ref().to_path_buf() }
+    }
+}
+
+impl SourceProvider for FsProvider {
+    fn list(&self, options: &ScanOptions) -> io::Result<Vec<PathBuf>> {
+        SqlFiles::with_options(&self.root, &[], options).map(Vec::from)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+/// A [`SourceProvider`] holding files in memory, keyed by their path.
+///
+/// Listing applies the [`ScanOptions`] as if the paths were relative to a
+/// scanned directory, see [`ScanOptions::is_scanned`].
+///
+/// # Examples
+/// ```
+/// use sql_docs::{SqlDoc, GenericDialect, provider::MemoryProvider};
+///
+/// let files = MemoryProvider::new()
+///     .with_file("app/users.sql", "-- Accounts\nCREATE TABLE users (id INTEGER);")
+///     .with_file("README.md", "not sql");
+/// let doc = SqlDoc::from_provider(&files).build::<GenericDialect>().unwrap();
+/// assert_eq!(doc.table("users", None).unwrap().doc(), Some("Accounts"));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemoryProvider {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryProvider {
+    /// Creates an empty provider
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { files: BTreeMap::new() }
+    }
+
+    /// Adds a file, replacing any file already at `path`
+    ///
+    /// # Parameters
+    /// - `path`: the path reported for the file's tables
+    /// - `content`: the raw file content
+    #[must_use]
+    pub fn with_file<P: Into<PathBuf>, C: Into<Vec<u8>>>(mut self, path: P, content: C) -> Self {
+        self.insert(path, content);
+        self
+    }
+
+    /// Adds a file, returning the content it replaced at `path`, if any
+    ///
+    /// # Parameters
+    /// - `path`: the path reported for the file's tables
+    /// - `content`: the raw file content
+    pub fn insert<P: Into<PathBuf>, C: Into<Vec<u8>>>(
+        &mut self,
+        path: P,
+        content: C,
+    ) -> Option<Vec<u8>> {
+        self.files.insert(path.into(), content.into())
+    }
+
+    /// Returns the number of files held
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if no files are held
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl<P: Into<PathBuf>, C: Into<Vec<u8>>> FromIterator<(P, C)> for MemoryProvider {
+    fn from_iter<I: IntoIterator<Item = (P, C)>>(iter: I) -> Self {
+        Self {
+            files: iter.into_iter().map(|(path, content)| (path.into(), content.into())).collect(),
+        }
+    }
+}
+
+impl SourceProvider for MemoryProvider {
+    fn list(&self, options: &ScanOptions) -> io::Result<Vec<PathBuf>> {
+        Ok(self.files.keys().filter(|path| options.is_scanned(path)).cloned().collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.get(path).cloned().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+        })
+    }
+}
+
+/// A [`SourceProvider`] over the files of a tar or zip archive, read into
+/// memory up front. Tables are reported with their path inside the archive.
+///
+/// Requires the `tar` or `zip` crate feature for the respective format.
+#[cfg(any(feature = "tar", feature = "zip"))]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ArchiveProvider {
+    files: MemoryProvider,
+}
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+impl ArchiveProvider {
+    /// Opens the archive at `path`, choosing the format from its extension:
+    /// `.zip`, `.tar`, or a compressed tarball such as `.tar.gz`, `.tgz` or
+    /// `.tar.zst` when the matching compression feature is enabled
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the archive cannot be read, or of kind
+    ///   [`io::ErrorKind::Unsupported`] if its format is unknown or its crate
+    ///   feature is disabled
+    pub fn open(path: &Path) -> io::Result<Self> {
+        match lowercase_extension(path).as_deref() {
+            Some("zip") => read_zip(path),
+            Some("tar") => read_tar(path, &fs::read(path)?),
+            Some("tgz") => read_tar(path, &Compression::Gzip.read(path)?),
+            Some("gz" | "zst")
+                if path
+                    .file_stem()
+                    .and_then(|stem| lowercase_extension(Path::new(stem)))
+                    .is_some_and(|ext| ext == "tar") =>
+            {
+                read_tar(path, &decompress_for(path, fs::read(path)?)?)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} is not a tar or zip archive", path.display()),
+            )),
+        }
+    }
+
+    /// Reads every regular file of an uncompressed tar archive, skipping
+    /// entries whose name would escape the archive such as `../schema.sql`
+    /// or `/etc/schema.sql`
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the archive is malformed
+    #[cfg(feature = "tar")]
+    pub fn tar<R: io::Read>(reader: R) -> io::Result<Self> {
+        use std::io::Read;
+
+        let mut files = MemoryProvider::new();
+        for entry in tar::Archive::new(reader).entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let Some(path) = enclosed_path(&entry.path()?) else {
+                continue;
+            };
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            files.insert(path, content);
+        }
+        Ok(Self { files })
+    }
+
+    /// Reads every file of a zip archive, skipping entries whose name would
+    /// escape the archive such as `../schema.sql`
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the archive is malformed or uses an
+    ///   unsupported compression method
+    #[cfg(feature = "zip")]
+    pub fn zip<R: io::Read + io::Seek>(reader: R) -> io::Result<Self> {
+        use std::io::Read;
+
+        let mut archive = zip::ZipArchive::new(reader)?;
+        let mut files = MemoryProvider::new();
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+            let Some(path) = entry.enclosed_name().filter(|_| entry.is_file()) else {
+                continue;
+            };
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            files.insert(path, content);
+        }
+        Ok(Self { files })
+    }
+}
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+impl SourceProvider for ArchiveProvider {
+    fn list(&self, options: &ScanOptions) -> io::Result<Vec<PathBuf>> {
+        self.files.list(options)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.read(path)
+    }
+}
+
+/// Normalizes the name of a tar entry like zip's `enclosed_name`: `.` is
+/// dropped and `..` resolved, and names that are absolute, empty or climb
+/// above the archive root are rejected
+#[cfg(feature = "tar")]
+fn enclosed_path(name: &Path) -> Option<PathBuf> {
+    use std::path::Component;
+
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !path.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (path.components().next().is_some()).then_some(path)
+}
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn lowercase_extension(path: &Path) -> Option<String> {
+    path.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+}
+
+#[cfg(feature = "tar")]
+fn read_tar(_path: &Path, tarball: &[u8]) -> io::Result<ArchiveProvider> {
+    ArchiveProvider::tar(tarball)
+}
+
+#[cfg(all(feature = "zip", not(feature = "tar")))]
+fn read_tar(path: &Path, _tarball: &[u8]) -> io::Result<ArchiveProvider> {
+    Err(unsupported_archive(path, "tar"))
+}
+
+#[cfg(feature = "zip")]
+fn read_zip(path: &Path) -> io::Result<ArchiveProvider> {
+    ArchiveProvider::zip(fs::File::open(path)?)
+}
+
+#[cfg(all(feature = "tar", not(feature = "zip")))]
+fn read_zip(path: &Path) -> io::Result<ArchiveProvider> {
+    Err(unsupported_archive(path, "zip"))
+}
+
+/// The error returned when opening an archive without the crate feature for
+/// its format
+#[cfg(not(all(feature = "tar", feature = "zip")))]
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn unsupported_archive(path: &Path, feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "{} is a {feature} archive; enable the `{feature}` feature to read it",
+            path.display()
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+    };
+
+    use crate::{
+        files::ScanOptions,
+        provider::{FsProvider, MemoryProvider, SourceProvider},
+    };
+
+    #[test]
+    fn test_memory_provider_lists_like_a_scan() -> Result<(), Box<dyn std::error::Error>> {
+        let files: MemoryProvider = [
+            ("app/users.sql", "CREATE TABLE users (id INTEGER);"),
+            ("app/archive/old.sql", "CREATE TABLE old (id INTEGER);"),
+            ("app/.hidden/h.sql", "CREATE TABLE h (id INTEGER);"),
+            ("target/out.sql", "CREATE TABLE out (id INTEGER);"),
+            ("notes.txt", "not sql"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(files.len(), 5);
+        assert_eq!(
+            files.list(&ScanOptions::new())?,
+            vec![PathBuf::from("app/archive/old.sql"), PathBuf::from("app/users.sql")]
+        );
+        assert_eq!(
+            files.list(&ScanOptions::new().exclude("**/archive/**"))?,
+            vec![PathBuf::from("app/users.sql")]
+        );
+        assert!(files.list(&ScanOptions::new().max_depth(1))?.is_empty());
+        assert_eq!(files.read(Path::new("notes.txt"))?, b"not sql");
+        let err = files.read(Path::new("missing.sql")).err();
+        assert_eq!(err.map(|e| e.kind()), Some(std::io::ErrorKind::NotFound));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fs_provider_lists_from_root() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("fs_provider_lists_from_root");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("sub"))?;
+        fs::write(base.join("a.sql"), "CREATE TABLE a (id INTEGER);")?;
+        fs::write(base.join("sub/b.sql"), "CREATE TABLE b (id INTEGER);")?;
+        let provider = FsProvider::new(&base);
+        assert_eq!(provider.root(), Some(base.as_path()));
+        assert_eq!(
+            provider.list(&ScanOptions::new())?,
+            vec![base.join("a.sql"), base.join("sub/b.sql")]
+        );
+        assert_eq!(provider.read(&base.join("a.sql"))?, b"CREATE TABLE a (id INTEGER);");
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "tar", feature = "zip"))]
+    fn test_archive_provider_reads_tar_and_zip() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        use crate::provider::ArchiveProvider;
+
+        let base = env::temp_dir().join("archive_provider_reads_tar_and_zip");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        let sql = b"CREATE TABLE users (id INTEGER);";
+
+        let mut tarball = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(sql.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tarball.append_data(&mut header, "schema/users.sql", &sql[..])?;
+        fs::write(base.join("schema.tar"), tarball.into_inner()?)?;
+
+        let mut zipped = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zipped.start_file("schema/users.sql", zip::write::SimpleFileOptions::default())?;
+        zipped.write_all(sql)?;
+        fs::write(base.join("schema.zip"), zipped.finish()?.into_inner())?;
+
+        for archive in ["schema.tar", "schema.zip"] {
+            let provider = ArchiveProvider::open(&base.join(archive))?;
+            let listed = provider.list(&ScanOptions::new())?;
+            assert_eq!(listed, vec![PathBuf::from("schema/users.sql")]);
+            assert_eq!(provider.read(&listed[0])?, sql);
+        }
+        let unknown = ArchiveProvider::open(&base.join("schema.rar")).err();
+        assert_eq!(unknown.map(|e| e.kind()), Some(std::io::ErrorKind::Unsupported));
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+    #[test]
+    #[cfg(feature = "tar")]
+    fn test_enclosed_path_rejects_escaping_tar_entries() {
+        use crate::provider::enclosed_path;
+
+        assert_eq!(
+            enclosed_path(Path::new("./schema/./old/../users.sql")),
+            Some(PathBuf::from("schema/users.sql"))
+        );
+        assert_eq!(enclosed_path(Path::new("../users.sql")), None);
+        assert_eq!(enclosed_path(Path::new("schema/../../users.sql")), None);
+        assert_eq!(enclosed_path(Path::new("/etc/users.sql")), None);
+        assert_eq!(enclosed_path(Path::new("schema/..")), None);
+    }
+}
@@ -0,0 +1,643 @@
+//! Render a [`SqlDoc`] into documentation formats.
+//!
+//! - [`markdown`] — Markdown pages, one per table or combined into one file
+//! - [`html`]     — A static, searchable HTML site that works offline
+//! - [`dot`]      — A Graphviz graph with a record per table and column-to-column keys
+//! - [`mermaid`]  — A Mermaid `erDiagram` of the tables and their foreign keys
+//! - [`dbml`]     — DBML for dbdiagram.io and dbdocs.io, with notes, refs and table groups
+//! - [`csv`]      — A CSV or TSV data dictionary with one row per column
+//! - `templates`  — User-written Jinja-like templates for any other format (`templates` feature)
+//!
+//! Renderers that produce several files return them as [`OutputFile`]s,
+//! which can be inspected in memory or written with [`write_files`].
+//!
+//! Every format also implements [`Renderer`], so it can be driven by
+//! [`render_with`] and looked up by name in a [`Registry`], next to formats
+//! registered by the application.
+
+use core::fmt;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    docs::{ColumnDoc, ForeignKey, TableDoc},
+    error::DocError,
+    sql_doc::SqlDoc,
+};
+
+pub mod csv;
+pub mod dbml;
+pub mod dot;
+pub mod html;
+pub mod markdown;
+pub mod mermaid;
+#[cfg(feature = "templates")]
+pub mod templates;
+
+/// The title of the index when a renderer is not given one
+pub const DEFAULT_TITLE: &str = "Database Documentation";
+
+/// A file produced by a renderer, with a path relative to the output directory
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutputFile {
+    path: PathBuf,
+    contents: String,
+}
+
+impl OutputFile {
+    /// Creates a new [`OutputFile`]
+    ///
+    /// # Parameters
+    /// - `path`: the path relative to the output directory, e.g. `index.md`
+    /// - `contents`: the rendered contents
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>, contents: String) -> Self {
+        Self { path: path.into(), contents }
+    }
+
+    /// Getter for the path relative to the output directory
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Getter for the rendered contents
+    #[must_use]
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+}
+
+/// Writes `files` below `dir`, creating it and any missing parent directories
+///
+/// # Parameters
+/// - `files`: the rendered files
+/// - `dir`: the output directory
+///
+/// # Errors
+/// - Returns [`DocError::FileReadError`] if a directory or file cannot be written
+pub fn write_files<P: AsRef<Path> + ?Sized>(files: &[OutputFile], dir: &P) -> Result<(), DocError> {
+    for file in files {
+        let path = dir.as_ref().join(file.path());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, file.contents())?;
+    }
+    Ok(())
+}
+
+/// Where a [`Renderer`] writes its output: a set of files, by path relative
+/// to the output directory
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Sink {
+    files: Vec<OutputFile>,
+}
+
+impl Sink {
+    /// Creates an empty [`Sink`]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    /// Returns the contents of the file at `path` to append to, creating the
+    /// file if it was not written yet
+    pub fn file(&mut self, path: impl Into<PathBuf>) -> &mut String {
+        let path = path.into();
+        let index = self.files.iter().position(|file| file.path == path).unwrap_or_else(|| {
+            self.files.push(OutputFile::new(path, String::new()));
+            self.files.len() - 1
+        });
+        &mut self.files[index].contents
+    }
+
+    /// Appends every file in `files` to the file at the same path
+    pub fn extend(&mut self, files: impl IntoIterator<Item = OutputFile>) {
+        for file in files {
+            self.file(file.path).push_str(&file.contents);
+        }
+    }
+
+    /// Returns the files written so far, in the order they were created
+    #[must_use]
+    pub fn into_files(self) -> Vec<OutputFile> {
+        self.files
+    }
+}
+
+/// A documentation format, driven by [`render_with`] through hooks.
+///
+/// The `object` hook is called first with the whole [`SqlDoc`], then `table`
+/// for every table followed by `column` for each of its columns in table
+/// order, and `index` last, once everything has been seen. Every hook does
+/// nothing by default, so a format only implements the ones it needs. The
+/// built-in Markdown and HTML formats write each table's page from `table`
+/// and the CSV format each row from `table` and `column`, while the diagrams
+/// and JSON, which need every table at once, render from `object`.
+///
+/// # Examples
+/// ```
+/// use core::fmt::Write as _;
+///
+/// use sql_docs::{ColumnDoc, DocError, GenericDialect, SqlDoc, TableDoc};
+/// use sql_docs::render::{Registry, Renderer, Sink};
+///
+/// /// Lists every column as `table.column`
+/// #[derive(Default)]
+/// struct Columns;
+///
+/// impl Renderer for Columns {
+///     fn column(
+///         &mut self,
+///         _: &SqlDoc,
+///         table: &TableDoc,
+///         column: &ColumnDoc,
+///         sink: &mut Sink,
+///     ) -> Result<(), DocError> {
+///         let out = sink.file("columns.txt");
+///         let _ = writeln!(out, "{}.{}", table.qualified_name(), column.name());
+///         Ok(())
+///     }
+/// }
+///
+/// let mut registry = Registry::new();
+/// registry.register("columns", || Columns);
+/// let doc = SqlDoc::builder_from_str("CREATE TABLE users (id INTEGER, name TEXT);")
+///     .build::<GenericDialect>()
+///     .unwrap();
+/// let files = registry.render("columns", &doc).unwrap();
+/// assert_eq!(files[0].contents(), "users.id\nusers.name\n");
+/// assert_eq!(registry.render("markdown", &doc).unwrap()[0].path().to_str(), Some("index.md"));
+/// ```
+pub trait Renderer {
+    /// Called once, before any other hook, with the whole [`SqlDoc`]
+    ///
+    /// # Errors
+    /// - Returns a [`DocError`] if the output cannot be rendered
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        let _ = (doc, sink);
+        Ok(())
+    }
+
+    /// Called for every table, in the order of [`SqlDoc::tables`]
+    ///
+    /// # Errors
+    /// - Returns a [`DocError`] if the output cannot be rendered
+    fn table(&mut self, doc: &SqlDoc, table: &TableDoc, sink: &mut Sink) -> Result<(), DocError> {
+        let _ = (doc, table, sink);
+        Ok(())
+    }
+
+    /// Called for every column of `table`, right after the `table` hook and
+    /// in the order of [`TableDoc::columns_in_order`]
+    ///
+    /// # Errors
+    /// - Returns a [`DocError`] if the output cannot be rendered
+    fn column(
+        &mut self,
+        doc: &SqlDoc,
+        table: &TableDoc,
+        column: &ColumnDoc,
+        sink: &mut Sink,
+    ) -> Result<(), DocError> {
+        let _ = (doc, table, column, sink);
+        Ok(())
+    }
+
+    /// Called once, after every table and column, e.g. to write an index
+    ///
+    /// # Errors
+    /// - Returns a [`DocError`] if the output cannot be rendered
+    fn index(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        let _ = (doc, sink);
+        Ok(())
+    }
+}
+
+/// Runs the hooks of `renderer` over `doc` and returns the files it wrote
+///
+/// # Parameters
+/// - `renderer`: the format to render
+/// - `doc`: the [`SqlDoc`] to render
+///
+/// # Errors
+/// - Returns the first [`DocError`] returned by a hook
+pub fn render_with<R: Renderer + ?Sized>(
+    renderer: &mut R,
+    doc: &SqlDoc,
+) -> Result<Vec<OutputFile>, DocError> {
+    let mut sink = Sink::new();
+    renderer.object(doc, &mut sink)?;
+    for table in doc.tables() {
+        renderer.table(doc, table, &mut sink)?;
+        for column in table.columns_in_order() {
+            renderer.column(doc, table, column, &mut sink)?;
+        }
+    }
+    renderer.index(doc, &mut sink)?;
+    Ok(sink.into_files())
+}
+
+/// Creates a fresh [`Renderer`] for every render
+type Factory = Box<dyn Fn() -> Box<dyn Renderer> + Send + Sync>;
+
+/// [`Renderer`]s keyed by format name, so a library or command line tool can
+/// select built-in and application formats uniformly.
+///
+/// Names are trimmed and matched case-insensitively. [`Registry::new`]
+/// starts with the built-in formats:
+///
+/// | Name       | Output                                                  |
+/// |------------|---------------------------------------------------------|
+/// | `markdown` | [`markdown::Markdown`] pages, one per table              |
+/// | `html`     | [`html::Html`] static site                               |
+/// | `mermaid`  | [`mermaid::Mermaid`] diagram in [`mermaid::OUTPUT_FILE`] |
+/// | `dot`      | [`dot::Dot`] graph in [`dot::OUTPUT_FILE`]               |
+/// | `dbml`     | [`dbml::Dbml`] in [`dbml::OUTPUT_FILE`]                  |
+/// | `csv`      | [`csv::Csv`] data dictionary in [`csv::OUTPUT_FILE`]     |
+/// | `tsv`      | The same, tab separated, in [`csv::TSV_OUTPUT_FILE`]     |
+/// | `json`     | The versioned JSON document, with the `json` feature     |
+pub struct Registry {
+    factories: BTreeMap<String, Factory>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry").field("formats", &self.factories.keys()).finish()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// Creates a [`Registry`] holding the built-in formats
+    #[must_use]
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register("markdown", markdown::Markdown::new);
+        registry.register("html", html::Html::new);
+        registry.register("mermaid", mermaid::Mermaid::new);
+        registry.register("dot", dot::Dot::new);
+        registry.register("dbml", dbml::Dbml::new);
+        registry.register("csv", csv::Csv::new);
+        registry.register("tsv", csv::Csv::tsv);
+        #[cfg(feature = "json")]
+        registry.register("json", || crate::json::Json);
+        registry
+    }
+
+    /// Creates a [`Registry`] without any format
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self { factories: BTreeMap::new() }
+    }
+
+    /// Registers `factory` under `name`, replacing any format with that name
+    ///
+    /// # Parameters
+    /// - `name`: the format name, e.g. `confluence`
+    /// - `factory`: creates the [`Renderer`], once per render
+    pub fn register<R, F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        R: Renderer + 'static,
+        F: Fn() -> R + Send + Sync + 'static,
+    {
+        let factory: Factory = Box::new(move || Box::new(factory()));
+        self.factories.insert(format_key(&name.into()), factory);
+    }
+
+    /// Returns the registered format names, in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    /// Creates the [`Renderer`] registered under `name`
+    ///
+    /// # Errors
+    /// - Returns [`DocError::UnknownFormat`] if no format has that name
+    pub fn renderer(&self, name: &str) -> Result<Box<dyn Renderer>, DocError> {
+        self.factories
+            .get(&format_key(name))
+            .map(|factory| factory())
+            .ok_or_else(|| DocError::UnknownFormat { name: name.to_owned() })
+    }
+
+    /// Renders `doc` in the format registered under `name`
+    ///
+    /// # Errors
+    /// - Returns [`DocError::UnknownFormat`] if no format has that name
+    /// - Returns any [`DocError`] raised by the renderer
+    pub fn render(&self, name: &str, doc: &SqlDoc) -> Result<Vec<OutputFile>, DocError> {
+        render_with(self.renderer(name)?.as_mut(), doc)
+    }
+
+    /// Renders `doc` in the format registered under `name` and writes the
+    /// files below `dir`
+    ///
+    /// # Errors
+    /// - Returns [`DocError::UnknownFormat`] if no format has that name
+    /// - Returns any [`DocError`] raised by the renderer
+    /// - Returns [`DocError::FileReadError`] if a directory or file cannot be written
+    pub fn write<P: AsRef<Path> + ?Sized>(
+        &self,
+        name: &str,
+        doc: &SqlDoc,
+        dir: &P,
+    ) -> Result<(), DocError> {
+        write_files(&self.render(name, doc)?, dir)
+    }
+}
+
+/// The key a format is registered and looked up by: `name` trimmed and lowercased
+fn format_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Returns the index of `table` in `doc`, if it is one of its tables rather
+/// than an equal copy
+pub(crate) fn table_index(doc: &SqlDoc, table: &TableDoc) -> Option<usize> {
+    doc.tables().iter().position(|t| core::ptr::eq(t, table))
+}
+
+/// Returns a unique, lowercase slug for every table in `tables`, usable as a
+/// file stem or anchor; tables sharing a qualified name get a numeric suffix,
+/// as do tables named `index` so their pages never replace the index page
+pub(crate) fn table_slugs(tables: &[TableDoc]) -> Vec<String> {
+    let mut taken: BTreeSet<String> = BTreeSet::from(["index".to_owned()]);
+    tables
+        .iter()
+        .map(|table| {
+            let base = slug(&table.qualified_name());
+            let mut slug = base.clone();
+            let mut count = 1;
+            while taken.contains(&slug) {
+                count += 1;
+                slug = format!("{base}-{count}");
+            }
+            taken.insert(slug.clone());
+            slug
+        })
+        .collect()
+}
+
+/// Returns a unique slug for every column of `table`, in the order of
+/// [`TableDoc::columns_in_order`]; columns whose names slug the same get a
+/// numeric suffix
+pub(crate) fn column_slugs(table: &TableDoc) -> Vec<String> {
+    let mut taken: BTreeSet<String> = BTreeSet::new();
+    table
+        .columns_in_order()
+        .into_iter()
+        .map(|column| {
+            let base = slug(column.name());
+            let mut slug = base.clone();
+            let mut count = 1;
+            while taken.contains(&slug) {
+                count += 1;
+                slug = format!("{base}-{count}");
+            }
+            taken.insert(slug.clone());
+            slug
+        })
+        .collect()
+}
+
+/// Lowercases `text` and replaces everything but letters, digits and `_`
+/// with `-`
+pub(crate) fn slug(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+/// Groups the indexes of `doc`'s tables by schema, tables without a schema
+/// first, each group ordered by table name
+pub(crate) fn tables_by_schema(doc: &SqlDoc) -> BTreeMap<Option<&str>, Vec<usize>> {
+    let mut groups: BTreeMap<Option<&str>, Vec<usize>> = BTreeMap::new();
+    for (index, table) in doc.tables().iter().enumerate() {
+        groups.entry(table.schema()).or_default().push(index);
+    }
+    groups
+}
+
+/// Finds the index of the table `key` references from `from` in `tables`.
+///
+/// A key without a schema prefers a table in the schema of `from`, then one
+/// without a schema, then any table with the referenced name.
+pub(crate) fn referenced_table(
+    tables: &[TableDoc],
+    from: &TableDoc,
+    key: &ForeignKey,
+) -> Option<usize> {
+    let named: Vec<usize> = tables
+        .iter()
+        .enumerate()
+        .filter(|(_, table)| table.name() == key.table())
+        .map(|(index, _)| index)
+        .collect();
+    let in_schema =
+        |schema: Option<&str>| named.iter().copied().find(|&i| tables[i].schema() == schema);
+    key.schema().map_or_else(
+        || in_schema(from.schema()).or_else(|| in_schema(None)).or_else(|| named.first().copied()),
+        |schema| in_schema(Some(schema)),
+    )
+}
+
+/// Returns the indexes of the tables with a foreign key referencing the
+/// table at `index`
+pub(crate) fn referencing_tables(tables: &[TableDoc], index: usize) -> Vec<usize> {
+    tables
+        .iter()
+        .enumerate()
+        .filter(|(_, table)| {
+            table
+                .columns()
+                .iter()
+                .filter_map(ColumnDoc::foreign_key)
+                .any(|key| referenced_table(tables, table, key) == Some(index))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// A foreign key between two tables of a [`SqlDoc`], by table index
+pub(crate) struct Reference<'a> {
+    /// The index of the table holding the foreign key
+    pub(crate) from: usize,
+    /// The column holding the foreign key
+    pub(crate) column: &'a ColumnDoc,
+    /// The index of the referenced table
+    pub(crate) to: usize,
+}
+
+/// Returns every foreign key in `tables` whose referenced table is also in
+/// `tables`, in table and column order
+pub(crate) fn references(tables: &[TableDoc]) -> Vec<Reference<'_>> {
+    tables
+        .iter()
+        .enumerate()
+        .flat_map(|(from, table)| {
+            table.columns_in_order().into_iter().filter_map(move |column| {
+                let key = column.foreign_key()?;
+                referenced_table(tables, table, key).map(|to| Reference { from, column, to })
+            })
+        })
+        .collect()
+}
+
+/// The schema the renderer tests share: `app.users` has a source, a
+/// multi-line doc and docs most formats must escape, `app.orders`
+/// references it and an unknown table, `app.order_lines` references
+/// `app.orders`, `app.profiles` shares the key of `app.users`, `memberships`
+/// has a composite key and `logs` has no columns
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write as _;
+    use std::{env, fs};
+
+    use crate::{
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        error::DocError,
+        render::{
+            OutputFile, Registry, Renderer, Sink, referenced_table, render_with, table_slugs,
+            write_files,
+        },
+        sql_doc::SqlDoc,
+    };
+
+    fn table(schema: Option<&str>, name: &str) -> TableDoc {
+        TableDoc::new(schema.map(str::to_owned), name.to_owned(), None, vec![], None)
+    }
+
+    #[test]
+    fn test_table_slugs_are_unique() {
+        let tables =
+            [table(Some("App"), "Users"), table(None, "user list"), table(Some("app"), "users")];
+        assert_eq!(table_slugs(&tables), ["app-users", "user-list", "app-users-2"]);
+        let tables = [table(None, "Index"), table(None, "index-2"), table(None, "index")];
+        assert_eq!(table_slugs(&tables), ["index-2", "index-2-2", "index-3"]);
+    }
+
+    #[test]
+    fn test_referenced_table_prefers_own_schema() {
+        let tables =
+            [table(None, "users"), table(Some("app"), "users"), table(Some("ops"), "logs")];
+        let key = |schema: Option<&str>, name: &str| {
+            ForeignKey::new(schema.map(str::to_owned), name.to_owned(), None)
+        };
+        assert_eq!(referenced_table(&tables, &tables[2], &key(Some("app"), "users")), Some(1));
+        assert_eq!(referenced_table(&tables, &tables[1], &key(None, "users")), Some(1));
+        assert_eq!(referenced_table(&tables, &tables[2], &key(None, "users")), Some(0));
+        assert_eq!(referenced_table(&tables, &tables[0], &key(None, "missing")), None);
+        assert_eq!(referenced_table(&tables, &tables[0], &key(Some("ops"), "users")), None);
+    }
+
+    #[test]
+    fn test_write_files_creates_directories() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("render_write_files_creates_directories");
+        let _ = fs::remove_dir_all(&base);
+        let files = [
+            OutputFile::new("index.md", "# Index".to_owned()),
+            OutputFile::new("tables/users.md", "# users".to_owned()),
+        ];
+        write_files(&files, &base)?;
+        assert_eq!(fs::read_to_string(base.join("index.md"))?, "# Index");
+        assert_eq!(fs::read_to_string(base.join("tables/users.md"))?, "# users");
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    /// Records every hook call, writing tables and columns to separate files
+    #[derive(Default)]
+    struct Trace;
+
+    impl Renderer for Trace {
+        fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+            let _ = writeln!(sink.file("trace.txt"), "object {}", doc.tables().len());
+            Ok(())
+        }
+
+        fn table(&mut self, _: &SqlDoc, table: &TableDoc, sink: &mut Sink) -> Result<(), DocError> {
+            let _ = writeln!(sink.file("trace.txt"), "table {}", table.name());
+            Ok(())
+        }
+
+        fn column(
+            &mut self,
+            _: &SqlDoc,
+            table: &TableDoc,
+            column: &ColumnDoc,
+            sink: &mut Sink,
+        ) -> Result<(), DocError> {
+            let _ = writeln!(sink.file("trace.txt"), "column {}", column.name());
+            sink.file(format!("{}.txt", table.name())).push_str(column.name());
+            Ok(())
+        }
+
+        fn index(&mut self, _: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+            sink.file("trace.txt").push_str("index\n");
+            Ok(())
+        }
+    }
+
+    fn sample() -> SqlDoc {
+        let column = |name: &str| ColumnDoc::new(name.to_owned(), None);
+        SqlDoc::new(vec![
+            TableDoc::new(None, "users".to_owned(), None, vec![column("name"), column("id")], None),
+            TableDoc::new(None, "logs".to_owned(), None, vec![], None),
+        ])
+    }
+
+    #[test]
+    fn test_render_with_calls_hooks_in_order() -> Result<(), DocError> {
+        let files = render_with(&mut Trace, &sample())?;
+        let paths: Vec<_> = files.iter().map(|f| f.path().to_string_lossy().into_owned()).collect();
+        assert_eq!(paths, ["trace.txt", "users.txt"]);
+        assert_eq!(
+            files[0].contents(),
+            "object 2\ntable logs\ntable users\ncolumn name\ncolumn id\nindex\n"
+        );
+        assert_eq!(files[1].contents(), "nameid");
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_selects_formats_by_name() -> Result<(), DocError> {
+        let mut registry = Registry::new();
+        let builtins: Vec<_> = registry.names().collect();
+        assert!(builtins.contains(&"markdown") && builtins.contains(&"tsv"));
+        assert_eq!(builtins.contains(&"json"), cfg!(feature = "json"));
+
+        registry.register(" Trace ", || Trace);
+        assert!(registry.names().any(|name| name == "trace"));
+        assert_eq!(registry.render("TRACE", &sample())?[0].path().to_str(), Some("trace.txt"));
+        let single = |name: &str| -> Result<Vec<String>, DocError> {
+            Ok(registry
+                .render(name, &sample())?
+                .iter()
+                .map(|file| file.path().to_string_lossy().into_owned())
+                .collect())
+        };
+        assert_eq!(single("mermaid")?, ["schema.mmd"]);
+        assert_eq!(single("dot")?, ["schema.dot"]);
+        assert_eq!(single("dbml")?, ["schema.dbml"]);
+        assert_eq!(single("csv")?, ["data_dictionary.csv"]);
+        assert_eq!(single("tsv")?, ["data_dictionary.tsv"]);
+        assert_eq!(single("html")?.first().map(String::as_str), Some("index.html"));
+        assert_eq!(single("markdown")?, ["index.md", "logs.md", "users.md"]);
+
+        match Registry::empty().render("markdown", &sample()) {
+            Err(DocError::UnknownFormat { name }) => assert_eq!(name, "markdown"),
+            other => panic!("expected UnknownFormat, got {other:?}"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,375 @@
+//! Export a [`SqlDoc`] as a CSV or TSV data dictionary.
+//!
+//! The dictionary has one row per column, in table order and then column
+//! order, with the [`Field`]s chosen by [`Csv::fields`]. As in RFC 4180, rows
+//! end with `\r\n` and fields holding the delimiter, a quote or a line break
+//! are quoted, so docs collected with
+//! [`MultiFlatten::NoFlat`](crate::MultiFlatten::NoFlat) keep their line
+//! breaks and still open as a single cell in spreadsheets.
+//!
+//! Spreadsheets run a cell starting with `=`, `+`, `-` or `@` as a formula,
+//! so by default such fields get a leading `'` unless they are a number, see
+//! [`Csv::sanitize_formulas`].
+
+use std::borrow::Cow;
+
+use crate::{
+    docs::{ColumnDoc, TableDoc},
+    error::DocError,
+    render::{Renderer, Sink},
+    sql_doc::SqlDoc,
+};
+
+/// The file a comma separated dictionary is written to through [`Renderer`]
+pub const OUTPUT_FILE: &str = "data_dictionary.csv";
+
+/// The file a tab separated dictionary is written to through [`Renderer`]
+pub const TSV_OUTPUT_FILE: &str = "data_dictionary.tsv";
+
+/// A field of the data dictionary
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Field {
+    /// The schema of the table, empty without one
+    Schema,
+    /// The name of the table
+    Table,
+    /// The name of the column
+    Column,
+    /// The 1-based position of the column in its table
+    Ordinal,
+    /// The data type of the column
+    Type,
+    /// `yes` or `no`
+    Nullable,
+    /// The default expression of the column
+    Default,
+    /// The doc of the table
+    TableDoc,
+    /// The doc of the column
+    ColumnDoc,
+    /// The path of the file the table was parsed from
+    SourcePath,
+    /// The line of the `CREATE TABLE` statement in that file
+    Line,
+}
+
+impl Field {
+    /// Every field, in the default order of the dictionary
+    pub const ALL: [Self; 11] = [
+        Self::Schema,
+        Self::Table,
+        Self::Column,
+        Self::Ordinal,
+        Self::Type,
+        Self::Nullable,
+        Self::Default,
+        Self::TableDoc,
+        Self::ColumnDoc,
+        Self::SourcePath,
+        Self::Line,
+    ];
+
+    /// The name of the field in the header row
+    #[must_use]
+    pub const fn header(self) -> &'static str {
+        match self {
+            Self::Schema => "schema",
+            Self::Table => "table",
+            Self::Column => "column",
+            Self::Ordinal => "ordinal",
+            Self::Type => "type",
+            Self::Nullable => "nullable",
+            Self::Default => "default",
+            Self::TableDoc => "table_doc",
+            Self::ColumnDoc => "column_doc",
+            Self::SourcePath => "source_path",
+            Self::Line => "line",
+        }
+    }
+
+    /// The value of the field for `column` of `table`; a table without
+    /// columns gets a single row whose column fields are empty
+    fn value<'a>(self, table: &'a TableDoc, column: Option<&'a ColumnDoc>) -> Cow<'a, str> {
+        let text = |value: Option<&'a str>| Cow::Borrowed(value.unwrap_or_default());
+        match self {
+            Self::Schema => text(table.schema()),
+            Self::Table => Cow::Borrowed(table.name()),
+            Self::Column => text(column.map(ColumnDoc::name)),
+            Self::Ordinal => column.map_or(Cow::Borrowed(""), |c| c.ordinal().to_string().into()),
+            Self::Type => text(column.and_then(ColumnDoc::data_type)),
+            Self::Nullable => text(column.map(|c| if c.is_nullable() { "yes" } else { "no" })),
+            Self::Default => text(column.and_then(ColumnDoc::default)),
+            Self::TableDoc => text(table.doc()),
+            Self::ColumnDoc => text(column.and_then(ColumnDoc::doc)),
+            Self::SourcePath => table
+                .path()
+                .map_or(Cow::Borrowed(""), |path| path.to_string_lossy().replace('\\', "/").into()),
+            Self::Line => table.line().map_or(Cow::Borrowed(""), |line| line.to_string().into()),
+        }
+    }
+}
+
+/// Exports a [`SqlDoc`] as a CSV or TSV data dictionary
+///
+/// # Examples
+/// ```
+/// use sql_docs::{GenericDialect, SqlDoc, render::csv::{Csv, Field}};
+///
+/// let sql = "-- Accounts\nCREATE TABLE users (\n  -- Key\n  id INTEGER PRIMARY KEY\n);";
+/// let doc = SqlDoc::builder_from_str(sql).build::<GenericDialect>().unwrap();
+/// let csv = Csv::new().fields([Field::Table, Field::Column, Field::ColumnDoc]).render(&doc);
+/// assert_eq!(csv, "table,column,column_doc\r\nusers,id,Key\r\n");
+/// let tsv = Csv::tsv().header(false).render(&doc);
+/// assert_eq!(tsv, "\tusers\tid\t1\tINTEGER\tno\t\tAccounts\tKey\t\t2\r\n");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Csv {
+    delimiter: char,
+    fields: Vec<Field>,
+    header: bool,
+    sanitize_formulas: bool,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Csv {
+    /// Creates a comma separated exporter with every [`Field`] and a header row
+    #[must_use]
+    pub fn new() -> Self {
+        Self { delimiter: ',', fields: Field::ALL.to_vec(), header: true, sanitize_formulas: true }
+    }
+
+    /// Creates a tab separated exporter with every [`Field`] and a header row
+    #[must_use]
+    pub fn tsv() -> Self {
+        Self::new().delimiter('\t')
+    }
+
+    /// Sets the character separating fields
+    #[must_use]
+    pub const fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets which fields are exported, in order
+    #[must_use]
+    pub fn fields(mut self, fields: impl IntoIterator<Item = Field>) -> Self {
+        self.fields = fields.into_iter().collect();
+        self
+    }
+
+    /// Sets whether the first row names the fields
+    #[must_use]
+    pub const fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Sets whether fields that a spreadsheet would run as a formula, i.e.
+    /// that start with `=`, `+`, `-` or `@` and are not a number, get a
+    /// leading `'`; on by default
+    #[must_use]
+    pub const fn sanitize_formulas(mut self, sanitize: bool) -> Self {
+        self.sanitize_formulas = sanitize;
+        self
+    }
+
+    /// Renders `doc` as one row per column, each row ending with `\r\n`
+    #[must_use]
+    pub fn render(&self, doc: &SqlDoc) -> String {
+        let mut out = String::new();
+        self.write_header(&mut out);
+        for table in doc.tables() {
+            self.write_table_row(&mut out, table);
+            for column in table.columns_in_order() {
+                self.write_column_row(&mut out, table, column);
+            }
+        }
+        out
+    }
+
+    /// The file this exporter writes to through [`Renderer`]
+    const fn output_file(&self) -> &'static str {
+        if self.delimiter == '\t' { TSV_OUTPUT_FILE } else { OUTPUT_FILE }
+    }
+
+    fn write_header(&self, out: &mut String) {
+        if self.header {
+            self.write_row(out, self.fields.iter().map(|field| Cow::Borrowed(field.header())));
+        }
+    }
+
+    /// Writes the row of a table without columns; tables with columns get a
+    /// row per column instead
+    fn write_table_row(&self, out: &mut String, table: &TableDoc) {
+        if table.columns().is_empty() {
+            self.write_row(out, self.fields.iter().map(|field| field.value(table, None)));
+        }
+    }
+
+    fn write_column_row(&self, out: &mut String, table: &TableDoc, column: &ColumnDoc) {
+        self.write_row(out, self.fields.iter().map(|field| field.value(table, Some(column))));
+    }
+
+    fn write_row<'a>(&self, out: &mut String, values: impl Iterator<Item = Cow<'a, str>>) {
+        for (i, value) in values.enumerate() {
+            if i > 0 {
+                out.push(self.delimiter);
+            }
+            let value = if self.sanitize_formulas && is_formula(&value) {
+                Cow::Owned(format!("'{value}"))
+            } else {
+                value
+            };
+            if value.contains([self.delimiter, '"', '\n', '\r']) {
+                out.push('"');
+                out.push_str(&value.replace('"', "\"\""));
+                out.push('"');
+            } else {
+                out.push_str(&value);
+            }
+        }
+        out.push_str("\r\n");
+    }
+}
+
+/// Whether a spreadsheet would run `value` as a formula
+fn is_formula(value: &str) -> bool {
+    value.starts_with(['=', '+', '-', '@']) && value.parse::<f64>().is_err()
+}
+
+impl Renderer for Csv {
+    fn object(&mut self, _: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        self.write_header(sink.file(self.output_file()));
+        Ok(())
+    }
+
+    fn table(&mut self, _: &SqlDoc, table: &TableDoc, sink: &mut Sink) -> Result<(), DocError> {
+        self.write_table_row(sink.file(self.output_file()), table);
+        Ok(())
+    }
+
+    fn column(
+        &mut self,
+        _: &SqlDoc,
+        table: &TableDoc,
+        column: &ColumnDoc,
+        sink: &mut Sink,
+    ) -> Result<(), DocError> {
+        self.write_column_row(sink.file(self.output_file()), table, column);
+        Ok(())
+    }
+}
+
+impl SqlDoc {
+    /// Renders this [`SqlDoc`] as a CSV data dictionary with every [`Field`],
+    /// see [`Csv`]
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        Csv::new().render(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        docs::{ColumnDoc, TableDoc},
+        error::DocError,
+        render::{
+            csv::{Csv, Field},
+            render_with,
+        },
+        sql_doc::SqlDoc,
+    };
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Registered accounts\nOne row per login".to_owned()),
+            vec![
+                ColumnDoc::new("id".to_owned(), Some("Key".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+                ColumnDoc::new("email".to_owned(), Some("The \"login\", unique".to_owned()))
+                    .with_data_type("VARCHAR(255)")
+                    .with_default("''"),
+            ],
+            Some(PathBuf::from("sql/users.sql")),
+        )
+        .with_line(3);
+        let logs =
+            TableDoc::new(None, "logs".to_owned(), Some("Audit\ttrail".to_owned()), vec![], None);
+        SqlDoc::new(vec![users, logs])
+    }
+
+    #[test]
+    fn test_render_csv_quotes_multiline_docs() {
+        assert_eq!(
+            sample().to_csv(),
+            "schema,table,column,ordinal,type,nullable,default,table_doc,column_doc,source_path,line\r\n\
+             ,logs,,,,,,Audit\ttrail,,,\r\n\
+             app,users,id,1,INTEGER,no,,\"Registered accounts\nOne row per login\",Key,sql/users.sql,3\r\n\
+             app,users,email,2,VARCHAR(255),yes,'',\"Registered accounts\nOne row per login\",\
+             \"The \"\"login\"\", unique\",sql/users.sql,3\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_csv_sanitizes_formulas() {
+        let doc = SqlDoc::new(vec![TableDoc::new(
+            None,
+            "=cmd".to_owned(),
+            Some("@SUM(A1:A2)".to_owned()),
+            vec![
+                ColumnDoc::new("total".to_owned(), Some("+1 for \"a, b\"".to_owned()))
+                    .with_default("-1.5"),
+                ColumnDoc::new("delta".to_owned(), None).with_default("-abs(1)"),
+            ],
+            None,
+        )]);
+        let csv =
+            Csv::new().fields([Field::Table, Field::TableDoc, Field::ColumnDoc, Field::Default]);
+        assert_eq!(
+            csv.render(&doc),
+            "table,table_doc,column_doc,default\r\n\
+             '=cmd,'@SUM(A1:A2),\"'+1 for \"\"a, b\"\"\",-1.5\r\n\
+             '=cmd,'@SUM(A1:A2),,'-abs(1)\r\n"
+        );
+        assert_eq!(
+            csv.sanitize_formulas(false).header(false).render(&doc),
+            "=cmd,@SUM(A1:A2),\"+1 for \"\"a, b\"\"\",-1.5\r\n=cmd,@SUM(A1:A2),,-abs(1)\r\n"
+        );
+    }
+
+    #[test]
+    fn test_renderer_hooks_write_the_same_rows() -> Result<(), DocError> {
+        let mut tsv = Csv::tsv().fields([Field::Table, Field::Column]);
+        let files = render_with(&mut tsv, &sample())?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path().to_str(), Some("data_dictionary.tsv"));
+        assert_eq!(files[0].contents(), tsv.render(&sample()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_tsv_with_selected_fields() {
+        let tsv = Csv::tsv()
+            .fields([Field::Line, Field::Table, Field::TableDoc])
+            .header(false)
+            .render(&sample());
+        assert_eq!(
+            tsv,
+            "\tlogs\t\"Audit\ttrail\"\r\n\
+             3\tusers\t\"Registered accounts\nOne row per login\"\r\n\
+             3\tusers\t\"Registered accounts\nOne row per login\"\r\n"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Render a [`SqlDoc`] as DBML, the language of dbdiagram.io and dbdocs.io.
+//!
+//! Every table becomes a `Table` with its columns, their types and their
+//! `pk`, `not null` and `default` settings; a primary key over several
+//! columns becomes an `indexes` entry instead. Table docs become `Note:` blocks
+//! and column docs `note:` settings. Foreign keys become `Ref`s and every
+//! schema a `TableGroup`.
+
+use core::fmt::{self, Write as _};
+
+use crate::{
+    docs::{ColumnDoc, TableDoc},
+    error::DocError,
+    render::{Reference, Renderer, Sink, references, tables_by_schema},
+    sql_doc::SqlDoc,
+};
+
+/// The file [`Dbml`] output is written to through [`Renderer`]
+pub const OUTPUT_FILE: &str = "schema.dbml";
+
+/// Renders a [`SqlDoc`] as DBML
+///
+/// # Examples
+/// ```
+/// use sql_docs::{GenericDialect, SqlDoc, render::dbml::Dbml};
+///
+/// let sql = "-- Accounts\nCREATE TABLE users (\n  id INTEGER PRIMARY KEY\n);\n\
+///            CREATE TABLE posts (\n  user_id INTEGER NOT NULL REFERENCES users (id)\n);";
+/// let doc = SqlDoc::builder_from_str(sql).build::<GenericDialect>().unwrap();
+/// let dbml = Dbml::new().project("blog").render(&doc);
+/// assert!(dbml.starts_with("Project blog {\n"));
+/// assert!(dbml.contains("Table users {\n  id INTEGER [pk]\n\n  Note: 'Accounts'\n}\n"));
+/// assert!(dbml.contains("Ref: posts.user_id > users.id\n"));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Dbml {
+    project: Option<String>,
+}
+
+impl Dbml {
+    /// Creates a [`Dbml`] renderer without a `Project` block
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { project: None }
+    }
+
+    /// Starts the output with a `Project` block named `name`
+    #[must_use]
+    pub fn project(mut self, name: impl Into<String>) -> Self {
+        self.project = Some(name.into());
+        self
+    }
+
+    /// Renders `doc` as DBML
+    #[must_use]
+    pub fn render(&self, doc: &SqlDoc) -> String {
+        let mut out = String::new();
+        let _ = self.write_schema(&mut out, doc);
+        out
+    }
+
+    /// Writes the blocks of `doc` to `out`, separated by blank lines
+    fn write_schema(&self, out: &mut String, doc: &SqlDoc) -> fmt::Result {
+        let tables = doc.tables();
+        if let Some(project) = &self.project {
+            write!(out, "Project {} {{\n}}\n", identifier(project))?;
+        }
+        for table in tables {
+            start_block(out);
+            write_table(out, table)?;
+        }
+        let refs: String = references(tables)
+            .iter()
+            .filter_map(|reference| reference_line(tables, reference))
+            .collect();
+        if !refs.is_empty() {
+            start_block(out);
+            out.push_str(&refs);
+        }
+        for (schema, indexes) in tables_by_schema(doc) {
+            let Some(schema) = schema else { continue };
+            start_block(out);
+            writeln!(out, "TableGroup {} {{", identifier(schema))?;
+            for index in indexes {
+                writeln!(out, "  {}", table_name(&tables[index]))?;
+            }
+            out.push_str("}\n");
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Dbml {
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        sink.file(OUTPUT_FILE).push_str(&self.render(doc));
+        Ok(())
+    }
+}
+
+impl SqlDoc {
+    /// Renders this [`SqlDoc`] as DBML, see [`Dbml`]
+    #[must_use]
+    pub fn to_dbml(&self) -> String {
+        Dbml::new().render(self)
+    }
+}
+
+/// Separates the block about to be written from the previous one, if any
+fn start_block(out: &mut String) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+}
+
+fn write_table(out: &mut String, table: &TableDoc) -> fmt::Result {
+    writeln!(out, "Table {} {{", table_name(table))?;
+    let keys: Vec<&ColumnDoc> =
+        table.columns_in_order().into_iter().filter(|column| column.is_primary_key()).collect();
+    for column in table.columns_in_order() {
+        write!(
+            out,
+            "  {} {}",
+            identifier(column.name()),
+            column.data_type().map_or_else(|| "unspecified".to_owned(), column_type)
+        )?;
+        let settings = column_settings(column, keys.len() == 1);
+        if !settings.is_empty() {
+            write!(out, " [{}]", settings.join(", "))?;
+        }
+        out.push('\n');
+    }
+    if keys.len() > 1 {
+        let names: Vec<String> = keys.iter().map(|key| identifier(key.name())).collect();
+        write!(out, "\n  indexes {{\n    ({}) [pk]\n  }}\n", names.join(", "))?;
+    }
+    if let Some(doc) = table.doc() {
+        if !table.columns().is_empty() {
+            out.push('\n');
+        }
+        writeln!(out, "  Note: {}", string(doc, "  "))?;
+    }
+    out.push_str("}\n");
+    Ok(())
+}
+
+/// The inline settings of `column`; `pk` is only set for the sole primary key
+/// column, as composite keys are declared in `indexes`
+fn column_settings(column: &ColumnDoc, sole_key: bool) -> Vec<String> {
+    let mut settings = Vec::new();
+    if column.is_primary_key() && sole_key {
+        settings.push("pk".to_owned());
+    } else if !column.is_nullable() {
+        settings.push("not null".to_owned());
+    }
+    if let Some(default) = column.default() {
+        settings.push(format!("default: {}", default_value(default)));
+    }
+    if let Some(doc) = column.doc() {
+        settings.push(format!("note: {}", string(doc, "  ")));
+    }
+    settings
+}
+
+/// Writes `reference` as a many-to-one `Ref`, or a one-to-one `Ref` when the
+/// column is the whole primary key; a key without a referenced column points
+/// at the sole primary key column, and is left out without one
+fn reference_line(tables: &[TableDoc], reference: &Reference<'_>) -> Option<String> {
+    let column = reference.column;
+    let target = &tables[reference.to];
+    let target_column = match column.foreign_key().and_then(|key| key.column()) {
+        Some(name) => name,
+        None => sole_primary_key(target)?.name(),
+    };
+    let from = &tables[reference.from];
+    let one_to_one = column.is_primary_key()
+        && sole_primary_key(from).is_some_and(|key| key.name() == column.name());
+    Some(format!(
+        "Ref: {}.{} {} {}.{}\n",
+        table_name(from),
+        identifier(column.name()),
+        if one_to_one { "-" } else { ">" },
+        table_name(target),
+        identifier(target_column)
+    ))
+}
+
+fn sole_primary_key(table: &TableDoc) -> Option<&ColumnDoc> {
+    let mut keys = table.columns().iter().filter(|column| column.is_primary_key());
+    keys.next().filter(|_| keys.next().is_none())
+}
+
+fn table_name(table: &TableDoc) -> String {
+    table.schema().map_or_else(
+        || identifier(table.name()),
+        |schema| format!("{}.{}", identifier(schema), identifier(table.name())),
+    )
+}
+
+/// Leaves `name` as is when it is a plain DBML identifier and double quotes
+/// it otherwise
+fn identifier(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        name.to_owned()
+    } else {
+        format!("\"{}\"", name.replace('"', "\\\""))
+    }
+}
+
+/// Double quotes types DBML would otherwise split, such as `double precision`
+fn column_type(data_type: &str) -> String {
+    if data_type.chars().any(|c| c.is_whitespace() || c == '"' || c == '[') {
+        format!("\"{}\"", data_type.replace('"', "\\\""))
+    } else {
+        data_type.to_owned()
+    }
+}
+
+/// Converts a SQL default to a DBML one: string literals are requoted,
+/// numbers and `true`/`false`/`null` kept and anything else becomes a
+/// backticked expression
+fn default_value(default: &str) -> String {
+    let default = default.trim();
+    let is_number = default.parse::<f64>().is_ok() && default.chars().any(|c| c.is_ascii_digit());
+    let is_keyword = ["true", "false", "null"].iter().any(|k| default.eq_ignore_ascii_case(k));
+    default
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+        .filter(|_| default.len() > 1)
+        .map_or_else(
+            || {
+                if is_number || is_keyword {
+                    default.to_ascii_lowercase()
+                } else {
+                    format!("`{}`", default.replace('`', "\\`"))
+                }
+            },
+            |literal| string(&literal.replace("''", "'"), ""),
+        )
+}
+
+/// Quotes `text` as a DBML string, using a `'''` string indented by
+/// `indent` when it spans several lines
+fn string(text: &str, indent: &str) -> String {
+    let escaped = text.replace('\\', "\\\\");
+    if text.contains('\n') {
+        let body: Vec<String> = escaped
+            .replace("'''", "\\'''")
+            .lines()
+            .map(|line| format!("{indent}  {line}"))
+            .collect();
+        format!("'''\n{}\n{indent}'''", body.join("\n"))
+    } else {
+        format!("'{}'", escaped.replace('\'', "\\'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        render::dbml::{Dbml, default_value},
+        sql_doc::SqlDoc,
+    };
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Registered accounts\nOne row per login".to_owned()),
+            vec![
+                ColumnDoc::new("id".to_owned(), Some("Key".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+                ColumnDoc::new("email".to_owned(), Some("User's login".to_owned()))
+                    .with_data_type("CHARACTER VARYING(255)")
+                    .with_nullable(false)
+                    .with_default("'n/a'"),
+                ColumnDoc::new("created at".to_owned(), None)
+                    .with_data_type("TIMESTAMP")
+                    .with_default("now()"),
+            ],
+            None,
+        );
+        let profiles = TableDoc::new(
+            Some("app".to_owned()),
+            "profiles".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("user_id".to_owned(), None)
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true)
+                    .with_foreign_key(ForeignKey::new(None, "users".to_owned(), None)),
+            ],
+            None,
+        );
+        let logs = TableDoc::new(
+            None,
+            "logs".to_owned(),
+            Some("Audit trail".to_owned()),
+            vec![
+                ColumnDoc::new("user_id".to_owned(), Some("Actor\nif any".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_default("0")
+                    .with_foreign_key(ForeignKey::new(
+                        Some("app".to_owned()),
+                        "users".to_owned(),
+                        Some("id".to_owned()),
+                    )),
+                ColumnDoc::new("sku".to_owned(), None).with_foreign_key(ForeignKey::new(
+                    None,
+                    "products".to_owned(),
+                    None,
+                )),
+            ],
+            None,
+        );
+        let memberships = TableDoc::new(
+            None,
+            "memberships".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("user_id".to_owned(), None)
+                    .with_data_type("INTEGER")
+                    .with_nullable(false)
+                    .with_primary_key(true)
+                    .with_foreign_key(ForeignKey::new(
+                        Some("app".to_owned()),
+                        "users".to_owned(),
+                        Some("id".to_owned()),
+                    )),
+                ColumnDoc::new("group_id".to_owned(), None)
+                    .with_data_type("INTEGER")
+                    .with_nullable(false)
+                    .with_primary_key(true),
+            ],
+            None,
+        );
+        SqlDoc::new(vec![users, profiles, logs, memberships])
+    }
+
+    #[test]
+    fn test_render_tables_refs_and_groups() {
+        let expected = "Project \"Shop DB\" {\n}\n\
+             \n\
+             Table logs {\n\
+             \x20 user_id INTEGER [default: 0, note: '''\n\
+             \x20   Actor\n\
+             \x20   if any\n\
+             \x20 ''']\n\
+             \x20 sku unspecified\n\
+             \n\
+             \x20 Note: 'Audit trail'\n\
+             }\n\
+             \n\
+             Table memberships {\n\
+             \x20 user_id INTEGER [not null]\n\
+             \x20 group_id INTEGER [not null]\n\
+             \n\
+             \x20 indexes {\n\
+             \x20   (user_id, group_id) [pk]\n\
+             \x20 }\n\
+             }\n\
+             \n\
+             Table app.profiles {\n\
+             \x20 user_id INTEGER [pk]\n\
+             }\n\
+             \n\
+             Table app.users {\n\
+             \x20 id INTEGER [pk, note: 'Key']\n\
+             \x20 email \"CHARACTER VARYING(255)\" [not null, default: 'n/a', note: 'User\\'s login']\n\
+             \x20 \"created at\" TIMESTAMP [default: `now()`]\n\
+             \n\
+             \x20 Note: '''\n\
+             \x20   Registered accounts\n\
+             \x20   One row per login\n\
+             \x20 '''\n\
+             }\n\
+             \n\
+             Ref: logs.user_id > app.users.id\n\
+             Ref: memberships.user_id > app.users.id\n\
+             Ref: app.profiles.user_id - app.users.id\n\
+             \n\
+             TableGroup app {\n\
+             \x20 app.profiles\n\
+             \x20 app.users\n\
+             }\n";
+        assert_eq!(Dbml::new().project("Shop DB").render(&sample()), expected);
+        assert!(sample().to_dbml().starts_with("Table logs {\n"));
+    }
+
+    #[test]
+    fn test_default_value() {
+        assert_eq!(default_value("'it''s'"), "'it\\'s'");
+        assert_eq!(default_value("-1.5"), "-1.5");
+        assert_eq!(default_value("TRUE"), "true");
+        assert_eq!(default_value("CURRENT_TIMESTAMP"), "`CURRENT_TIMESTAMP`");
+        assert_eq!(default_value("'"), "`'`");
+        assert_eq!(default_value("NaN"), "`NaN`");
+    }
+}
@@ -0,0 +1,357 @@
+//! Render a [`SqlDoc`] as a Graphviz DOT graph.
+//!
+//! Every table becomes a node drawn as a record of its columns, with each
+//! column row a port named by the column's slug, so foreign keys are drawn
+//! from the referencing column to the referenced one. Tables are grouped into
+//! a cluster per schema. The table doc becomes the node tooltip and every
+//! column doc the tooltip of its row, so they show up on hover in SVG output,
+//! e.g. `dot -Tsvg schema.dot -o schema.svg`.
+//!
+//! The records are HTML-like tables on `shape=plain` nodes rather than
+//! `shape=record` labels, as only those can carry a tooltip per column.
+//! Graphviz only shows the tooltip of a cell that has a link, so documented
+//! columns link to `#`.
+
+use core::fmt::{self, Write as _};
+
+use crate::{
+    docs::{ColumnDoc, TableDoc},
+    error::DocError,
+    render::{
+        DEFAULT_TITLE, Reference, Renderer, Sink, column_slugs, html::escape, references,
+        table_slugs, tables_by_schema,
+    },
+    sql_doc::SqlDoc,
+};
+
+/// The file a [`Dot`] graph is written to through [`Renderer`]
+pub const OUTPUT_FILE: &str = "schema.dot";
+
+/// Renders a [`SqlDoc`] as a Graphviz `digraph`
+///
+/// # Examples
+/// ```
+/// use sql_docs::{GenericDialect, SqlDoc, render::dot::Dot};
+///
+/// let sql = "CREATE TABLE users (\n  id INTEGER PRIMARY KEY\n);\n\
+///            CREATE TABLE posts (\n  -- Author\n  user_id INTEGER REFERENCES users (id)\n);";
+/// let doc = SqlDoc::builder_from_str(sql).build::<GenericDialect>().unwrap();
+/// let graph = Dot::new().title("Blog").render(&doc);
+/// assert!(graph.starts_with("digraph \"Blog\" {\n"));
+/// assert!(graph.contains("<TD PORT=\"user_id\" ALIGN=\"LEFT\" HREF=\"#\" TOOLTIP=\"Author\">"));
+/// assert!(graph.contains("    \"posts\":\"user_id\" -> \"users\":\"id\""));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dot {
+    title: String,
+}
+
+impl Default for Dot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dot {
+    /// Creates a [`Dot`] renderer with the [`DEFAULT_TITLE`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self { title: DEFAULT_TITLE.to_owned() }
+    }
+
+    /// Sets the name and label of the graph
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Renders `doc` as a `digraph` with a cluster per schema
+    #[must_use]
+    pub fn render(&self, doc: &SqlDoc) -> String {
+        let mut out = String::new();
+        let _ = self.write_graph(&mut out, doc);
+        out
+    }
+
+    fn write_graph(&self, out: &mut String, doc: &SqlDoc) -> fmt::Result {
+        let tables = doc.tables();
+        let ids = table_slugs(tables);
+        let title = quote(&self.title);
+        writeln!(out, "digraph {title} {{")?;
+        writeln!(out, "    graph [label={title}, labelloc=t, rankdir=LR];")?;
+        out.push_str("    node [shape=plain];\n");
+        for (schema, indexes) in tables_by_schema(doc) {
+            let indent = if let Some(schema) = schema {
+                writeln!(out, "\n    subgraph {} {{", quote(&format!("cluster_{schema}")))?;
+                writeln!(out, "        label={};", quote(schema))?;
+                "        "
+            } else {
+                out.push('\n');
+                "    "
+            };
+            for index in indexes {
+                write_node(out, indent, &tables[index], &ids[index])?;
+            }
+            if schema.is_some() {
+                out.push_str("    }\n");
+            }
+        }
+        let references = references(tables);
+        if !references.is_empty() {
+            out.push('\n');
+        }
+        for reference in &references {
+            write_edge(out, tables, &ids, reference)?;
+        }
+        out.push_str("}\n");
+        Ok(())
+    }
+}
+
+impl Renderer for Dot {
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        sink.file(OUTPUT_FILE).push_str(&self.render(doc));
+        Ok(())
+    }
+}
+
+impl SqlDoc {
+    /// Renders this [`SqlDoc`] as a Graphviz `digraph`, see [`Dot`]
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        Dot::new().render(self)
+    }
+}
+
+fn write_node(out: &mut String, indent: &str, table: &TableDoc, id: &str) -> fmt::Result {
+    write!(out, "{indent}{} [", quote(id))?;
+    if let Some(doc) = table.doc() {
+        write!(out, "tooltip={}, ", quote(doc))?;
+    }
+    writeln!(
+        out,
+        "label=<\n{indent}    <TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\">"
+    )?;
+    writeln!(
+        out,
+        "{indent}    <TR><TD BGCOLOR=\"#f6f8fa\"><B>{}</B></TD></TR>",
+        escape(table.name())
+    )?;
+    for column in table.columns_in_order() {
+        let port = port(table, column.name()).unwrap_or_default();
+        write!(out, "{indent}    <TR><TD PORT=\"{port}\" ALIGN=\"LEFT\"")?;
+        if let Some(doc) = column.doc() {
+            write!(out, " HREF=\"#\" TOOLTIP=\"{}\"", escape(doc).replace('\n', "&#10;"))?;
+        }
+        writeln!(out, ">{}</TD></TR>", column_label(column))?;
+    }
+    writeln!(out, "{indent}    </TABLE>\n{indent}>];")
+}
+
+/// The text of a column row: its name, type and key markers
+fn column_label(column: &ColumnDoc) -> String {
+    let mut label = escape(column.name());
+    if let Some(data_type) = column.data_type() {
+        label.push_str(": ");
+        label.push_str(&escape(data_type));
+    }
+    match (column.is_primary_key(), column.foreign_key().is_some()) {
+        (true, true) => label.push_str(" PK, FK"),
+        (true, false) => label.push_str(" PK"),
+        (false, true) => label.push_str(" FK"),
+        (false, false) => {}
+    }
+    label
+}
+
+/// Writes `reference` as an edge between column ports; a key without a
+/// referenced column points at the sole primary key column, if any, or
+/// else at the table
+fn write_edge(
+    out: &mut String,
+    tables: &[TableDoc],
+    ids: &[String],
+    reference: &Reference<'_>,
+) -> fmt::Result {
+    let column = reference.column;
+    let target = &tables[reference.to];
+    let key = column.foreign_key().map(ToString::to_string).unwrap_or_default();
+    let target_port = column
+        .foreign_key()
+        .and_then(|key| key.column())
+        .or_else(|| {
+            let mut keys = target.columns().iter().filter(|c| c.is_primary_key());
+            keys.next().filter(|_| keys.next().is_none()).map(ColumnDoc::name)
+        })
+        .and_then(|name| port(target, name));
+    write!(
+        out,
+        "    {}:{} -> {}",
+        quote(&ids[reference.from]),
+        quote(&port(&tables[reference.from], column.name()).unwrap_or_default()),
+        quote(&ids[reference.to])
+    )?;
+    if let Some(target_port) = target_port {
+        write!(out, ":{}", quote(&target_port))?;
+    }
+    let from = format!("{}.{}", tables[reference.from].qualified_name(), column.name());
+    writeln!(out, " [tooltip={}];", quote(&format!("{from} → {key}")))
+}
+
+/// The port of the row of the column `name` in `table`'s node, or `None` if
+/// the table has no such column; ports are the unique column slugs, so they
+/// need no escaping in the label or on an edge
+fn port(table: &TableDoc, name: &str) -> Option<String> {
+    let position = table.columns_in_order().iter().position(|c| c.name() == name)?;
+    column_slugs(table).into_iter().nth(position)
+}
+
+/// Quotes `text` as a DOT string, keeping line breaks
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write as _,
+        process::{Command, Stdio},
+    };
+
+    use crate::{
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        render::dot::{Dot, quote},
+        sql_doc::SqlDoc,
+    };
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Registered \"accounts\"\nOne row per login".to_owned()),
+            vec![
+                ColumnDoc::new("id".to_owned(), Some("Key & <id>".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+                ColumnDoc::new("email".to_owned(), None).with_data_type("TEXT"),
+            ],
+            None,
+        );
+        let orders = TableDoc::new(
+            Some("app".to_owned()),
+            "orders".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("user_id".to_owned(), Some("Buyer\nof the order".to_owned()))
+                    .with_foreign_key(ForeignKey::new(None, "users".to_owned(), None)),
+                ColumnDoc::new("sku".to_owned(), None).with_foreign_key(ForeignKey::new(
+                    None,
+                    "products".to_owned(),
+                    None,
+                )),
+            ],
+            None,
+        );
+        let logs = TableDoc::new(None, "logs".to_owned(), None, vec![], None);
+        SqlDoc::new(vec![users, orders, logs])
+    }
+
+    #[test]
+    fn test_render_clusters_ports_and_tooltips() {
+        let expected = r##"digraph "Schema" {
+    graph [label="Schema", labelloc=t, rankdir=LR];
+    node [shape=plain];
+
+    "logs" [label=<
+        <TABLE BORDER="0" CELLBORDER="1" CELLSPACING="0" CELLPADDING="4">
+        <TR><TD BGCOLOR="#f6f8fa"><B>logs</B></TD></TR>
+        </TABLE>
+    >];
+
+    subgraph "cluster_app" {
+        label="app";
+        "app-orders" [label=<
+            <TABLE BORDER="0" CELLBORDER="1" CELLSPACING="0" CELLPADDING="4">
+            <TR><TD BGCOLOR="#f6f8fa"><B>orders</B></TD></TR>
+            <TR><TD PORT="user_id" ALIGN="LEFT" HREF="#" TOOLTIP="Buyer&#10;of the order">user_id FK</TD></TR>
+            <TR><TD PORT="sku" ALIGN="LEFT">sku FK</TD></TR>
+            </TABLE>
+        >];
+        "app-users" [tooltip="Registered \"accounts\"\nOne row per login", label=<
+            <TABLE BORDER="0" CELLBORDER="1" CELLSPACING="0" CELLPADDING="4">
+            <TR><TD BGCOLOR="#f6f8fa"><B>users</B></TD></TR>
+            <TR><TD PORT="id" ALIGN="LEFT" HREF="#" TOOLTIP="Key &amp; &lt;id&gt;">id: INTEGER PK</TD></TR>
+            <TR><TD PORT="email" ALIGN="LEFT">email: TEXT</TD></TR>
+            </TABLE>
+        >];
+    }
+
+    "app-orders":"user_id" -> "app-users":"id" [tooltip="app.orders.user_id → users"];
+}
+"##;
+        assert_eq!(Dot::new().title("Schema").render(&sample()), expected);
+    }
+
+    #[test]
+    fn test_ports_are_unique_column_slugs() {
+        let events = TableDoc::new(
+            None,
+            "events".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("created at".to_owned(), None),
+                ColumnDoc::new("Created \"at\"".to_owned(), None).with_primary_key(true),
+            ],
+            None,
+        );
+        let audit = TableDoc::new(
+            None,
+            "audit".to_owned(),
+            None,
+            vec![ColumnDoc::new("event at".to_owned(), None).with_foreign_key(ForeignKey::new(
+                None,
+                "events".to_owned(),
+                None,
+            ))],
+            None,
+        );
+        let graph = SqlDoc::new(vec![events, audit]).to_dot();
+        assert!(graph.contains("<TD PORT=\"created-at\" ALIGN=\"LEFT\">created at</TD>"));
+        assert!(graph.contains("<TD PORT=\"created--at-\" ALIGN=\"LEFT\">"));
+        assert!(graph.contains("\"audit\":\"event-at\" -> \"events\":\"created--at-\" ["));
+    }
+
+    #[test]
+    fn test_svg_shows_column_tooltips() -> Result<(), Box<dyn std::error::Error>> {
+        let Ok(mut dot) =
+            Command::new("dot").arg("-Tsvg").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()
+        else {
+            eprintln!("skipping test_svg_shows_column_tooltips: Graphviz `dot` is not installed");
+            return Ok(());
+        };
+        dot.stdin.take().ok_or("no stdin")?.write_all(sample().to_dot().as_bytes())?;
+        let svg = String::from_utf8(dot.wait_with_output()?.stdout)?;
+        assert!(svg.contains("xlink:title=\"Key &amp; &lt;id&gt;\""), "{svg}");
+        assert!(svg.contains("xlink:title=\"Buyer"), "{svg}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("a \"b\"\\c\r\nd"), "\"a \\\"b\\\"\\\\c\\nd\"");
+        assert_eq!(sample().to_dot().lines().next(), Some("digraph \"Database Documentation\" {"));
+    }
+}
@@ -0,0 +1,632 @@
+//! Render a [`SqlDoc`] as a static HTML site.
+//!
+//! The site consists of an `index.html` listing the tables by schema, one
+//! page per table with an anchor for every column, a stylesheet and a
+//! client-side search. The search index is written both as
+//! `search-index.json` for other tools and as `search-index.js`, which the
+//! pages load with a `<script>` tag so searching also works when the site is
+//! opened from `file://`. Nothing is loaded from a CDN or any other host.
+
+use core::fmt::{self, Write as _};
+use std::path::Path;
+
+use crate::{
+    docs::{ColumnDoc, TableDoc},
+    error::DocError,
+    render::{
+        DEFAULT_TITLE, OutputFile, Renderer, Sink, column_slugs, referenced_table,
+        referencing_tables, table_index, table_slugs, tables_by_schema, write_files,
+    },
+    sql_doc::SqlDoc,
+};
+
+/// The name of the page listing every table
+pub const INDEX_FILE: &str = "index.html";
+
+/// The name of the search index, a JSON array with one object per table and column
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+
+/// The search index wrapped in a script, since pages opened from `file://`
+/// cannot fetch JSON
+const SEARCH_INDEX_SCRIPT: &str = "search-index.js";
+const STYLE_FILE: &str = "style.css";
+const SEARCH_FILE: &str = "search.js";
+const STYLE: &str = include_str!("html/style.css");
+const SEARCH: &str = include_str!("html/search.js");
+
+/// Renders a [`SqlDoc`] as a static, self-contained HTML site
+///
+/// # Examples
+/// ```
+/// use sql_docs::{GenericDialect, SqlDoc, render::html::Html};
+///
+/// let sql = "-- Accounts\nCREATE TABLE users (\n  id INTEGER PRIMARY KEY\n);";
+/// let doc = SqlDoc::builder_from_str(sql).build::<GenericDialect>().unwrap();
+/// let files = Html::new().title("Shop").render(&doc);
+/// let page = files.iter().find(|f| f.path().to_str() == Some("users.html")).unwrap();
+/// assert!(page.contents().contains("<tr id=\"col-id\">"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Html {
+    title: String,
+    source_base: Option<String>,
+}
+
+impl Default for Html {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Html {
+    /// Creates an [`Html`] renderer with the [`DEFAULT_TITLE`] that links
+    /// sources by their path
+    #[must_use]
+    pub fn new() -> Self {
+        Self { title: DEFAULT_TITLE.to_owned(), source_base: None }
+    }
+
+    /// Sets the title shown on every page
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Prefixes the links to source files with `base`, and points them at
+    /// the line of the table, e.g.
+    /// `https://github.com/org/repo/blob/main/` links `sql/users.sql` to
+    /// `https://github.com/org/repo/blob/main/sql/users.sql#L3`.
+    ///
+    /// Without a base, links use the percent-encoded path, so relative paths
+    /// resolve against the output directory.
+    #[must_use]
+    pub fn source_base(mut self, base: impl Into<String>) -> Self {
+        self.source_base = Some(base.into());
+        self
+    }
+
+    /// Renders `doc` into the pages, the stylesheet, the search script and
+    /// the search index
+    #[must_use]
+    pub fn render(&self, doc: &SqlDoc) -> Vec<OutputFile> {
+        let site = Site::new(self, doc);
+        let mut sink = Sink::new();
+        site.write_shared_files(&mut sink);
+        for index in 0..doc.number_of_tables() {
+            site.write_table_page(&mut sink, index);
+        }
+        sink.into_files()
+    }
+
+    /// Renders `doc` and writes the site below `dir`
+    ///
+    /// # Errors
+    /// - Returns [`DocError::FileReadError`] if a directory or file cannot be written
+    pub fn write<P: AsRef<Path> + ?Sized>(&self, doc: &SqlDoc, dir: &P) -> Result<(), DocError> {
+        write_files(&self.render(doc), dir)
+    }
+}
+
+impl Renderer for Html {
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        Site::new(self, doc).write_shared_files(sink);
+        Ok(())
+    }
+
+    fn table(&mut self, doc: &SqlDoc, table: &TableDoc, sink: &mut Sink) -> Result<(), DocError> {
+        if let Some(index) = table_index(doc, table) {
+            Site::new(self, doc).write_table_page(sink, index);
+        }
+        Ok(())
+    }
+}
+
+/// Builds the search index of `doc` as a JSON array.
+///
+/// Every table and column gets an object holding its `kind` (`"table"` or
+/// `"column"`), `name`, qualified `table` name, `doc` and the `url` of its
+/// page or anchor.
+#[must_use]
+pub fn search_index(doc: &SqlDoc) -> String {
+    let slugs = table_slugs(doc.tables());
+    let mut entries = Vec::new();
+    for (table, slug) in doc.tables().iter().zip(&slugs) {
+        let qualified = table.qualified_name();
+        let url = format!("{slug}.html");
+        entries.push(search_entry("table", table.name(), &qualified, table.doc(), &url));
+        for (column, anchor) in table.columns_in_order().into_iter().zip(column_anchors(table)) {
+            let url = format!("{slug}.html#{anchor}");
+            entries.push(search_entry("column", column.name(), &qualified, column.doc(), &url));
+        }
+    }
+    if entries.is_empty() { "[]".to_owned() } else { format!("[\n{}\n]", entries.join(",\n")) }
+}
+
+fn search_entry(kind: &str, name: &str, table: &str, doc: Option<&str>, url: &str) -> String {
+    format!(
+        "  {{\"kind\": {}, \"name\": {}, \"table\": {}, \"doc\": {}, \"url\": {}}}",
+        json_string(kind),
+        json_string(name),
+        json_string(table),
+        doc.map_or_else(|| "null".to_owned(), json_string),
+        json_string(url),
+    )
+}
+
+/// The pages of a site and what they link to
+struct Site<'a> {
+    html: &'a Html,
+    doc: &'a SqlDoc,
+    slugs: Vec<String>,
+    nav: String,
+}
+
+impl<'a> Site<'a> {
+    fn new(html: &'a Html, doc: &'a SqlDoc) -> Self {
+        let slugs = table_slugs(doc.tables());
+        let mut nav = String::new();
+        for (schema, indexes) in tables_by_schema(doc) {
+            let _ = writeln!(nav, "<h2>{}</h2>", escape(schema.unwrap_or("Default schema")));
+            nav.push_str("<ul>\n");
+            for i in indexes {
+                let name = escape(doc.tables()[i].name());
+                let _ = writeln!(nav, "<li><a href=\"{}.html\">{name}</a></li>", slugs[i]);
+            }
+            nav.push_str("</ul>\n");
+        }
+        Self { html, doc, slugs, nav }
+    }
+
+    /// Writes the index page, the stylesheet, the search script and the
+    /// search index
+    fn write_shared_files(&self, sink: &mut Sink) {
+        let index = search_index(self.doc);
+        sink.file(INDEX_FILE).push_str(&self.page(&self.html.title, &self.index_body()));
+        sink.file(STYLE_FILE).push_str(STYLE);
+        sink.file(SEARCH_FILE).push_str(SEARCH);
+        let script = format!("window.sqlDocsSearchIndex = {index};\n");
+        sink.file(SEARCH_INDEX_SCRIPT).push_str(&script);
+        sink.file(SEARCH_INDEX_FILE).push_str(&index);
+    }
+
+    /// Writes the page of the table at `index`
+    fn write_table_page(&self, sink: &mut Sink, index: usize) {
+        let table = &self.doc.tables()[index];
+        let page = self.page(&table.qualified_name(), &self.table_body(index));
+        sink.file(format!("{}.html", self.slugs[index])).push_str(&page);
+    }
+
+    /// Wraps `body` into a complete page with the navigation and search
+    fn page(&self, heading: &str, body: &str) -> String {
+        let title = escape(&self.html.title);
+        let heading = escape(heading);
+        let page_title =
+            if heading == title { title.clone() } else { format!("{heading} — {title}") };
+        format!(
+            "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{page_title}</title>
+<link rel=\"stylesheet\" href=\"{STYLE_FILE}\">
+</head>
+<body>
+<nav>
+<a class=\"home\" href=\"{INDEX_FILE}\">{title}</a>
+<input id=\"search\" type=\"search\" placeholder=\"Search tables and columns\" \
+             autocomplete=\"off\" aria-label=\"Search tables and columns\">
+<ul id=\"search-results\"></ul>
+{nav}</nav>
+<main>
+{body}</main>
+<script src=\"{SEARCH_INDEX_SCRIPT}\"></script>
+<script src=\"{SEARCH_FILE}\"></script>
+</body>
+</html>
+",
+            nav = self.nav,
+        )
+    }
+
+    fn index_body(&self) -> String {
+        let mut out = String::new();
+        let _ = self.write_index_body(&mut out);
+        out
+    }
+
+    fn write_index_body(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "<h1>{}</h1>", escape(&self.html.title))?;
+        for (schema, indexes) in tables_by_schema(self.doc) {
+            writeln!(out, "<h2>{}</h2>", escape(schema.unwrap_or("Default schema")))?;
+            writeln!(out, "<table>\n<tr><th>Table</th><th>Description</th></tr>")?;
+            for i in indexes {
+                let table = &self.doc.tables()[i];
+                let summary = table.doc().and_then(|d| d.lines().next()).unwrap_or_default();
+                writeln!(
+                    out,
+                    "<tr><td><a href=\"{}.html\">{}</a></td><td>{}</td></tr>",
+                    self.slugs[i],
+                    escape(table.name()),
+                    escape(summary.trim()),
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+        Ok(())
+    }
+
+    fn table_body(&self, index: usize) -> String {
+        let mut out = String::new();
+        let _ = self.write_table_body(&mut out, index);
+        out
+    }
+
+    fn write_table_body(&self, out: &mut String, index: usize) -> fmt::Result {
+        let tables = self.doc.tables();
+        let table = &tables[index];
+        writeln!(out, "<h1>{}</h1>", escape(&table.qualified_name()))?;
+        if let Some(doc) = table.doc() {
+            writeln!(out, "{}", paragraphs(doc))?;
+        }
+        if let Some(path) = table.path() {
+            write!(
+                out,
+                "<p class=\"source\">Defined in <a href=\"{}\"><code>{}</code></a>",
+                escape(&self.source_href(path, table.line())),
+                escape(&path.to_string_lossy().replace('\\', "/")),
+            )?;
+            if let Some(line) = table.line() {
+                write!(out, " at line {line}")?;
+            }
+            writeln!(out, ".</p>")?;
+        }
+        writeln!(
+            out,
+            "<table>\n<tr><th>Column</th><th>Type</th><th>Nullable</th><th>Default</th>\
+             <th>Key</th><th>Description</th></tr>"
+        )?;
+        for (column, anchor) in table.columns_in_order().into_iter().zip(column_anchors(table)) {
+            writeln!(
+                out,
+                "<tr id=\"{anchor}\"><td><a href=\"#{anchor}\"><code>{}</code></a></td>\
+                 <td><code>{}</code></td><td>{}</td><td><code>{}</code></td><td>{}</td>\
+                 <td>{}</td></tr>",
+                escape(column.name()),
+                escape(column.data_type().unwrap_or_default()),
+                if column.is_nullable() { "yes" } else { "no" },
+                escape(column.default().unwrap_or_default()),
+                self.key_cell(table, column),
+                column.doc().map(paragraphs).unwrap_or_default(),
+            )?;
+        }
+        writeln!(out, "</table>")?;
+        let referencing: Vec<String> = referencing_tables(tables, index)
+            .into_iter()
+            .map(|i| {
+                let name = escape(&tables[i].qualified_name());
+                format!("<a href=\"{}.html\">{name}</a>", self.slugs[i])
+            })
+            .collect();
+        if !referencing.is_empty() {
+            writeln!(out, "<p>Referenced by: {}</p>", referencing.join(", "))?;
+        }
+        Ok(())
+    }
+
+    /// The `Key` cell of `column`: `PK` and a link to the column a foreign key references
+    fn key_cell(&self, table: &TableDoc, column: &ColumnDoc) -> String {
+        let mut keys = Vec::new();
+        if column.is_primary_key() {
+            keys.push("PK".to_owned());
+        }
+        if let Some(key) = column.foreign_key() {
+            let text = escape(&key.to_string());
+            let tables = self.doc.tables();
+            let target = referenced_table(tables, table, key);
+            keys.push(target.map_or_else(
+                || format!("FK → {text}"),
+                |target| {
+                    let anchor = key
+                        .column()
+                        .and_then(|name| {
+                            let columns = tables[target].columns_in_order();
+                            let position = columns.iter().position(|c| c.name() == name)?;
+                            column_anchors(&tables[target]).into_iter().nth(position)
+                        })
+                        .map(|anchor| format!("#{anchor}"))
+                        .unwrap_or_default();
+                    format!("FK → <a href=\"{}.html{anchor}\">{text}</a>", self.slugs[target])
+                },
+            ));
+        }
+        keys.join(", ")
+    }
+
+    fn source_href(&self, path: &Path, line: Option<u64>) -> String {
+        let path = percent_encode(&path.to_string_lossy().replace('\\', "/"));
+        match &self.html.source_base {
+            Some(base) => {
+                line.map_or_else(|| format!("{base}{path}"), |line| format!("{base}{path}#L{line}"))
+            }
+            None if path.starts_with('/') => format!("file://{path}"),
+            None => path,
+        }
+    }
+}
+
+/// The ids of the rows of `table`'s columns on its page, in the order of
+/// [`TableDoc::columns_in_order`]
+pub(super) fn column_anchors(table: &TableDoc) -> Vec<String> {
+    column_slugs(table).into_iter().map(|slug| format!("col-{slug}")).collect()
+}
+
+/// Percent-encodes the bytes of `path` that may not appear in a URL path
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/:".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+/// Escapes `text` for use in HTML text and attribute values
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Turns a comment into paragraphs, split at blank lines and keeping line breaks
+fn paragraphs(text: &str) -> String {
+    let mut out = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim).chain([""]) {
+        if line.is_empty() {
+            if !paragraph.is_empty() {
+                out.push(format!("<p>{}</p>", escape(&paragraph.join("\n")).replace('\n', "<br>")));
+                paragraph.clear();
+            }
+        } else {
+            paragraph.push(line);
+        }
+    }
+    out.join("\n")
+}
+
+/// Quotes `text` as a JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            // also escaped so the index stays valid inside a script
+            '<' | '\u{2028}' | '\u{2029}' => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use serde_json::Value;
+
+    use crate::{
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        error::DocError,
+        render::{
+            html::{Html, search_index},
+            render_with,
+        },
+        sql_doc::SqlDoc,
+    };
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Registered <accounts>\n\nOne row per login".to_owned()),
+            vec![
+                ColumnDoc::new("id".to_owned(), Some("Key".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+                ColumnDoc::new("email".to_owned(), Some("Login \"address\"".to_owned()))
+                    .with_data_type("TEXT"),
+            ],
+            Some(PathBuf::from("sql/users.sql")),
+        )
+        .with_line(3);
+        let orders = TableDoc::new(
+            Some("app".to_owned()),
+            "orders".to_owned(),
+            None,
+            vec![ColumnDoc::new("user_id".to_owned(), None).with_foreign_key(ForeignKey::new(
+                None,
+                "users".to_owned(),
+                Some("id".to_owned()),
+            ))],
+            None,
+        );
+        SqlDoc::new(vec![users, orders])
+    }
+
+    fn file<'a>(files: &'a [crate::render::OutputFile], path: &str) -> &'a str {
+        files
+            .iter()
+            .find(|f| f.path().to_str() == Some(path))
+            .unwrap_or_else(|| panic!("missing {path}"))
+            .contents()
+    }
+
+    #[test]
+    fn test_site_pages_link_tables_columns_and_sources() {
+        let files = Html::new().source_base("https://example.com/repo/").render(&sample());
+        let paths: Vec<_> = files.iter().map(|f| f.path().to_string_lossy().into_owned()).collect();
+        assert_eq!(
+            paths,
+            [
+                "index.html",
+                "style.css",
+                "search.js",
+                "search-index.js",
+                "search-index.json",
+                "app-orders.html",
+                "app-users.html"
+            ]
+        );
+        let index = file(&files, "index.html");
+        assert!(index.contains("<h2>app</h2>"));
+        assert!(index.contains(
+            "<td><a href=\"app-users.html\">users</a></td><td>Registered &lt;accounts&gt;</td>"
+        ));
+
+        let users = file(&files, "app-users.html");
+        assert!(users.contains("<title>app.users — Database Documentation</title>"));
+        assert!(users.contains("<p>Registered &lt;accounts&gt;</p>\n<p>One row per login</p>"));
+        assert!(users.contains(
+            "<a href=\"https://example.com/repo/sql/users.sql#L3\"><code>sql/users.sql</code></a> \
+             at line 3."
+        ));
+        assert!(
+            users.contains("<tr id=\"col-id\"><td><a href=\"#col-id\"><code>id</code></a></td>")
+        );
+        assert!(users.contains("<p>Login &quot;address&quot;</p>"));
+        assert!(users.contains("Referenced by: <a href=\"app-orders.html\">app.orders</a>"));
+        let orders = file(&files, "app-orders.html");
+        assert!(orders.contains("FK → <a href=\"app-users.html#col-id\">users.id</a>"));
+    }
+
+    #[test]
+    fn test_renderer_hooks_write_the_same_files() -> Result<(), DocError> {
+        let mut html = Html::new().title("Shop");
+        assert_eq!(render_with(&mut html, &sample())?, html.render(&sample()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_named_index_keeps_the_index_page() -> Result<(), DocError> {
+        let doc = SqlDoc::new(vec![TableDoc::new(None, "index".to_owned(), None, vec![], None)]);
+        let files = render_with(&mut Html::new(), &doc)?;
+        assert!(file(&files, "index.html").contains("<a href=\"index-2.html\">index</a>"));
+        assert!(file(&files, "index-2.html").contains("<title>index — Database Documentation"));
+        assert_eq!(files.iter().filter(|f| f.path().to_str() == Some("index.html")).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_site_is_self_contained() {
+        let files = Html::new().render(&sample());
+        for file in &files {
+            assert!(!file.contents().contains("http"), "{:?} loads a remote asset", file.path());
+        }
+        let users = file(&files, "app-users.html");
+        assert!(users.contains("<script src=\"search-index.js\"></script>"));
+        assert!(users.contains("<a href=\"sql/users.sql\">"));
+        let script = file(&files, "search-index.js");
+        assert!(script.starts_with("window.sqlDocsSearchIndex = [\n"));
+    }
+
+    #[test]
+    fn test_search_index_is_json() -> Result<(), Box<dyn std::error::Error>> {
+        let index: Value = serde_json::from_str(&search_index(&sample()))?;
+        let entries = index.as_array().ok_or("expected an array")?;
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0]["kind"], "table");
+        assert_eq!(entries[0]["doc"], Value::Null);
+        assert_eq!(entries[3]["name"], "id");
+        assert_eq!(entries[3]["table"], "app.users");
+        assert_eq!(entries[3]["url"], "app-users.html#col-id");
+        assert_eq!(entries[4]["doc"], "Login \"address\"");
+        assert_eq!(entries[2]["doc"], "Registered <accounts>\n\nOne row per login");
+        assert_eq!(search_index(&SqlDoc::new(vec![])), "[]");
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_anchors_are_unique() {
+        let table = TableDoc::new(
+            None,
+            "events".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("created at".to_owned(), None),
+                ColumnDoc::new("created-at".to_owned(), None),
+            ],
+            None,
+        );
+        let audit = TableDoc::new(
+            None,
+            "audit".to_owned(),
+            None,
+            vec![ColumnDoc::new("event".to_owned(), None).with_foreign_key(ForeignKey::new(
+                None,
+                "events".to_owned(),
+                Some("created-at".to_owned()),
+            ))],
+            None,
+        );
+        let files = Html::new().render(&SqlDoc::new(vec![table, audit]));
+        let events = file(&files, "events.html");
+        assert!(events.contains("<tr id=\"col-created-at\"><td><a href=\"#col-created-at\">"));
+        assert!(events.contains("<tr id=\"col-created-at-2\"><td><a href=\"#col-created-at-2\">"));
+        let audit = file(&files, "audit.html");
+        assert!(audit.contains("<a href=\"events.html#col-created-at-2\">events.created-at</a>"));
+        assert!(
+            file(&files, "search-index.json").contains("\"url\": \"events.html#col-created-at-2\"")
+        );
+    }
+
+    #[test]
+    fn test_source_links_are_percent_encoded() {
+        let table = TableDoc::new(
+            None,
+            "users".to_owned(),
+            None,
+            vec![],
+            Some(PathBuf::from("/srv/my sql/users#1.sql")),
+        );
+        let files = Html::new().render(&SqlDoc::new(vec![table]));
+        assert!(file(&files, "users.html").contains(
+            "<a href=\"file:///srv/my%20sql/users%231.sql\"><code>/srv/my sql/users#1.sql</code></a>"
+        ));
+    }
+
+    #[test]
+    fn test_write_creates_site() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("html_write_creates_site");
+        let _ = fs::remove_dir_all(&base);
+        Html::new().write(&sample(), &base)?;
+        assert!(fs::read_to_string(base.join("index.html"))?.starts_with("<!DOCTYPE html>"));
+        assert!(base.join("style.css").is_file() && base.join("search-index.json").is_file());
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+}
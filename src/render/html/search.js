@@ -0,0 +1,60 @@
+// Client-side search over the index loaded from search-index.js.
+// It only uses the DOM, so the site also works when opened from file://.
+(function () {
+  "use strict";
+
+  var MAX_RESULTS = 50;
+  var input = document.getElementById("search");
+  var results = document.getElementById("search-results");
+  var entries = window.sqlDocsSearchIndex || [];
+
+  function label(entry) {
+    return entry.kind === "column" ? entry.table + "." + entry.name : entry.table;
+  }
+
+  function search(query) {
+    var name = [];
+    var other = [];
+    for (var i = 0; i < entries.length; i++) {
+      var entry = entries[i];
+      if (entry.name.toLowerCase().indexOf(query) !== -1) {
+        name.push(entry);
+      } else if ((label(entry) + " " + (entry.doc || "")).toLowerCase().indexOf(query) !== -1) {
+        other.push(entry);
+      }
+    }
+    return name.concat(other).slice(0, MAX_RESULTS);
+  }
+
+  function render() {
+    var query = input.value.trim().toLowerCase();
+    results.textContent = "";
+    if (!query) {
+      return;
+    }
+    var matches = search(query);
+    if (!matches.length) {
+      var none = document.createElement("li");
+      none.textContent = "No matches";
+      results.appendChild(none);
+      return;
+    }
+    matches.forEach(function (entry) {
+      var item = document.createElement("li");
+      var link = document.createElement("a");
+      link.href = entry.url;
+      link.textContent = label(entry);
+      item.appendChild(link);
+      if (entry.doc) {
+        var summary = document.createElement("span");
+        summary.textContent = " — " + entry.doc.split("\n")[0];
+        item.appendChild(summary);
+      }
+      results.appendChild(item);
+    });
+  }
+
+  if (input && results) {
+    input.addEventListener("input", render);
+  }
+})();
@@ -0,0 +1,456 @@
+//! Render a [`SqlDoc`] as Markdown.
+//!
+//! Every table gets a section with its doc, where it is defined, a column
+//! table (name, type, nullability, default, keys and doc) and the tables
+//! referencing it. An index groups the tables by schema. With
+//! [`MarkdownLayout::PerTable`] the index is written to `index.md` and every
+//! table to its own file; with [`MarkdownLayout::Combined`] everything is
+//! written to `index.md`. Links between tables are relative, so the output
+//! can be committed next to the SQL or published as is.
+
+use core::fmt::{self, Write as _};
+use std::path::Path;
+
+use crate::{
+    docs::{ColumnDoc, TableDoc},
+    error::DocError,
+    render::{
+        DEFAULT_TITLE, OutputFile, Renderer, Sink, referenced_table, referencing_tables,
+        table_index, table_slugs, tables_by_schema, write_files,
+    },
+    sql_doc::SqlDoc,
+};
+
+/// The name of the index file, which holds every table with [`MarkdownLayout::Combined`]
+pub const INDEX_FILE: &str = "index.md";
+
+/// How [`Markdown::render`] splits its output into files
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MarkdownLayout {
+    /// An `index.md` linking to one file per table
+    #[default]
+    PerTable,
+    /// A single `index.md` holding the index and every table
+    Combined,
+}
+
+/// Renders a [`SqlDoc`] as Markdown
+///
+/// # Examples
+/// ```
+/// use sql_docs::{GenericDialect, SqlDoc, render::markdown::Markdown};
+///
+/// let sql = "-- Accounts\nCREATE TABLE users (\n  id INTEGER PRIMARY KEY\n);";
+/// let doc = SqlDoc::builder_from_str(sql).build::<GenericDialect>().unwrap();
+/// let files = Markdown::new().title("Shop").render(&doc);
+/// assert_eq!(files[0].path().to_str(), Some("index.md"));
+/// assert!(files[0].contents().contains("- [users](users.md) — Accounts"));
+/// assert!(files[1].contents().contains("| `id` | `INTEGER` | no |  | PK |  |"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Markdown {
+    title: String,
+    layout: MarkdownLayout,
+}
+
+impl Default for Markdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Markdown {
+    /// Creates a [`Markdown`] renderer with the [`DEFAULT_TITLE`] and one file per table
+    #[must_use]
+    pub fn new() -> Self {
+        Self { title: DEFAULT_TITLE.to_owned(), layout: MarkdownLayout::PerTable }
+    }
+
+    /// Sets the title of the index
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets how the output is split into files, see [`MarkdownLayout`]
+    #[must_use]
+    pub const fn layout(mut self, layout: MarkdownLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Renders `doc` into files, the index first, laid out as configured
+    /// with [`Markdown::layout`]
+    #[must_use]
+    pub fn render(&self, doc: &SqlDoc) -> Vec<OutputFile> {
+        let mut sink = Sink::new();
+        let _ = self.write_index_file(&mut sink, doc);
+        for index in 0..doc.number_of_tables() {
+            let _ = self.write_table_file(&mut sink, doc, index);
+        }
+        sink.into_files()
+    }
+
+    /// Renders the index of [`MarkdownLayout::PerTable`], linking to the
+    /// file of every table
+    #[must_use]
+    pub fn render_index(&self, doc: &SqlDoc) -> String {
+        let mut out = String::new();
+        let _ = self.write_index(&mut out, doc, &Links::new(doc, false));
+        out
+    }
+
+    /// Renders the index and every table into a single document, linking
+    /// to the tables through anchors
+    #[must_use]
+    pub fn render_combined(&self, doc: &SqlDoc) -> String {
+        let mut out = String::new();
+        let _ = self.write_combined(&mut out, doc);
+        out
+    }
+
+    /// Renders `doc` and writes the files below `dir`
+    ///
+    /// # Errors
+    /// - Returns [`DocError::FileReadError`] if a directory or file cannot be written
+    pub fn write<P: AsRef<Path> + ?Sized>(&self, doc: &SqlDoc, dir: &P) -> Result<(), DocError> {
+        write_files(&self.render(doc), dir)
+    }
+
+    fn write_combined(&self, out: &mut String, doc: &SqlDoc) -> fmt::Result {
+        let links = Links::new(doc, true);
+        self.write_index(out, doc, &links)?;
+        for index in 0..doc.number_of_tables() {
+            write_section(out, doc, index, &links)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the index to [`INDEX_FILE`] in `sink`
+    fn write_index_file(&self, sink: &mut Sink, doc: &SqlDoc) -> fmt::Result {
+        let combined = self.layout == MarkdownLayout::Combined;
+        self.write_index(sink.file(INDEX_FILE), doc, &Links::new(doc, combined))
+    }
+
+    /// Writes the table at `index` to its own file in `sink`, or appends it
+    /// to [`INDEX_FILE`] with [`MarkdownLayout::Combined`]
+    fn write_table_file(&self, sink: &mut Sink, doc: &SqlDoc, index: usize) -> fmt::Result {
+        match self.layout {
+            MarkdownLayout::Combined => {
+                write_section(sink.file(INDEX_FILE), doc, index, &Links::new(doc, true))
+            }
+            MarkdownLayout::PerTable => {
+                let links = Links::new(doc, false);
+                let path = format!("{}.md", links.slugs[index]);
+                write_table(sink.file(path), doc, index, &links, 1)
+            }
+        }
+    }
+
+    fn write_index(&self, out: &mut String, doc: &SqlDoc, links: &Links) -> fmt::Result {
+        writeln!(out, "# {}", escape(&self.title))?;
+        for (schema, indexes) in tables_by_schema(doc) {
+            writeln!(out, "\n## {}\n", schema.map_or_else(|| "Default schema".to_owned(), escape))?;
+            for index in indexes {
+                let table = &doc.tables()[index];
+                write!(out, "- [{}]({})", escape(table.name()), links.href(index))?;
+                if let Some(summary) = table.doc().and_then(|d| d.lines().next()) {
+                    write!(out, " — {}", escape(summary.trim()))?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Markdown {
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        let _ = self.write_index_file(sink, doc);
+        Ok(())
+    }
+
+    fn table(&mut self, doc: &SqlDoc, table: &TableDoc, sink: &mut Sink) -> Result<(), DocError> {
+        if let Some(index) = table_index(doc, table) {
+            let _ = self.write_table_file(sink, doc, index);
+        }
+        Ok(())
+    }
+}
+
+impl SqlDoc {
+    /// Renders this [`SqlDoc`] as a single Markdown document, see
+    /// [`Markdown::render_combined`]
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        Markdown::new().render_combined(self)
+    }
+}
+
+/// Where the tables of a [`SqlDoc`] are linked to
+struct Links {
+    slugs: Vec<String>,
+    combined: bool,
+}
+
+impl Links {
+    fn new(doc: &SqlDoc, combined: bool) -> Self {
+        Self { slugs: table_slugs(doc.tables()), combined }
+    }
+
+    fn href(&self, index: usize) -> String {
+        if self.combined {
+            format!("#{}", self.slugs[index])
+        } else {
+            format!("{}.md", self.slugs[index])
+        }
+    }
+}
+
+/// Writes the table at `index` as a section of the combined document,
+/// preceded by a rule and its anchor
+fn write_section(out: &mut String, doc: &SqlDoc, index: usize, links: &Links) -> fmt::Result {
+    out.push_str("\n---\n\n");
+    write!(out, "<a id=\"{}\"></a>\n\n", links.slugs[index])?;
+    write_table(out, doc, index, links, 2)
+}
+
+/// Writes the section of the table at `index` with a heading of `level`
+fn write_table(
+    out: &mut String,
+    doc: &SqlDoc,
+    index: usize,
+    links: &Links,
+    level: usize,
+) -> fmt::Result {
+    let tables = doc.tables();
+    let table = &tables[index];
+    writeln!(out, "{} {}\n", "#".repeat(level), escape(&table.qualified_name()))?;
+    if !links.combined {
+        writeln!(out, "[Index]({INDEX_FILE})\n")?;
+    }
+    if let Some(table_doc) = table.doc() {
+        writeln!(out, "{}\n", escape(table_doc))?;
+    }
+    if let Some(path) = table.path() {
+        let path = path.to_string_lossy().replace('\\', "/");
+        write!(out, "Defined in {}", code(&path))?;
+        if let Some(line) = table.line() {
+            write!(out, " at line {line}")?;
+        }
+        writeln!(out, ".\n")?;
+    }
+    writeln!(out, "| Column | Type | Nullable | Default | Key | Description |")?;
+    writeln!(out, "| --- | --- | --- | --- | --- | --- |")?;
+    for column in table.columns_in_order() {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} |",
+            cell(&code(column.name())),
+            cell(&column.data_type().map(code).unwrap_or_default()),
+            if column.is_nullable() { "yes" } else { "no" },
+            cell(&column.default().map(code).unwrap_or_default()),
+            key_cell(tables, table, column, links),
+            lines(&escape(column.doc().unwrap_or_default())),
+        )?;
+    }
+    let referencing: Vec<String> = referencing_tables(tables, index)
+        .into_iter()
+        .map(|i| format!("[{}]({})", escape(&tables[i].qualified_name()), links.href(i)))
+        .collect();
+    if !referencing.is_empty() {
+        writeln!(out, "\nReferenced by: {}", referencing.join(", "))?;
+    }
+    Ok(())
+}
+
+/// The `Key` cell of `column`: `PK` and a link to the table a foreign key references
+fn key_cell(tables: &[TableDoc], table: &TableDoc, column: &ColumnDoc, links: &Links) -> String {
+    let mut keys = Vec::new();
+    if column.is_primary_key() {
+        keys.push("PK".to_owned());
+    }
+    if let Some(key) = column.foreign_key() {
+        keys.push(referenced_table(tables, table, key).map_or_else(
+            || format!("FK → {}", escape(&key.to_string())),
+            |target| format!("FK → [{}]({})", escape(&key.to_string()), links.href(target)),
+        ));
+    }
+    cell(&keys.join(", "))
+}
+
+/// Escapes the characters that would start Markdown links, emphasis, code or HTML
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '[' | ']' | '<' | '>' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Wraps `text` in a code span, using a longer fence if it contains backticks
+fn code(text: &str) -> String {
+    if text.contains('`') { format!("`` {text} ``") } else { format!("`{text}`") }
+}
+
+/// Makes `text` fit into a table cell by escaping pipes and joining its lines with `<br>`
+fn cell(text: &str) -> String {
+    lines(&text.replace('|', "\\|"))
+}
+
+/// Joins the trimmed lines of `text` with `<br>`
+fn lines(text: &str) -> String {
+    text.trim().lines().map(str::trim).collect::<Vec<_>>().join("<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use crate::{
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        error::DocError,
+        render::{
+            markdown::{Markdown, MarkdownLayout},
+            render_with,
+        },
+        sql_doc::SqlDoc,
+    };
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Registered accounts\nOne row per login".to_owned()),
+            vec![
+                ColumnDoc::new("id".to_owned(), Some("Key".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+                ColumnDoc::new("email".to_owned(), Some("Login | contact\naddress".to_owned()))
+                    .with_data_type("TEXT")
+                    .with_default("''"),
+            ],
+            Some(PathBuf::from("sql/users.sql")),
+        )
+        .with_line(3);
+        let orders = TableDoc::new(
+            Some("app".to_owned()),
+            "orders".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("user_id".to_owned(), None)
+                    .with_data_type("INTEGER")
+                    .with_foreign_key(ForeignKey::new(
+                        None,
+                        "users".to_owned(),
+                        Some("id".to_owned()),
+                    )),
+                ColumnDoc::new("sku".to_owned(), None).with_foreign_key(ForeignKey::new(
+                    None,
+                    "products".to_owned(),
+                    None,
+                )),
+            ],
+            None,
+        );
+        let logs =
+            TableDoc::new(None, "logs".to_owned(), Some("Audit trail".to_owned()), vec![], None);
+        SqlDoc::new(vec![users, orders, logs])
+    }
+
+    #[test]
+    fn test_per_table_layout() {
+        let files = Markdown::new().render(&sample());
+        let paths: Vec<_> = files.iter().map(|f| f.path().to_string_lossy().into_owned()).collect();
+        assert_eq!(paths, ["index.md", "logs.md", "app-orders.md", "app-users.md"]);
+        assert_eq!(
+            files[0].contents(),
+            "# Database Documentation\n\n## Default schema\n\n- [logs](logs.md) — Audit trail\n\n\
+             ## app\n\n- [orders](app-orders.md)\n- [users](app-users.md) — Registered accounts\n"
+        );
+        assert_eq!(
+            files[3].contents(),
+            "# app.users\n\n[Index](index.md)\n\nRegistered accounts\nOne row per login\n\n\
+             Defined in `sql/users.sql` at line 3.\n\n\
+             | Column | Type | Nullable | Default | Key | Description |\n\
+             | --- | --- | --- | --- | --- | --- |\n\
+             | `id` | `INTEGER` | no |  | PK | Key |\n\
+             | `email` | `TEXT` | yes | `''` |  | Login \\| contact<br>address |\n\
+             \nReferenced by: [app.orders](app-orders.md)\n"
+        );
+        let orders = files[2].contents();
+        assert!(
+            orders
+                .contains("| `user_id` | `INTEGER` | yes |  | FK → [users.id](app-users.md) |  |")
+        );
+        assert!(orders.contains("| `sku` |  | yes |  | FK → products |  |"));
+    }
+
+    #[test]
+    fn test_table_named_index_keeps_the_index_page() -> Result<(), DocError> {
+        let doc = SqlDoc::new(vec![TableDoc::new(None, "Index".to_owned(), None, vec![], None)]);
+        let files = render_with(&mut Markdown::new(), &doc)?;
+        let paths: Vec<_> = files.iter().map(|f| f.path().to_string_lossy().into_owned()).collect();
+        assert_eq!(paths, ["index.md", "index-2.md"]);
+        assert!(files[0].contents().ends_with("- [Index](index-2.md)\n"));
+        assert!(files[1].contents().starts_with("# Index\n\n[Index](index.md)\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_renderer_hooks_write_the_same_files() -> Result<(), DocError> {
+        for layout in [MarkdownLayout::PerTable, MarkdownLayout::Combined] {
+            let mut markdown = Markdown::new().layout(layout);
+            assert_eq!(render_with(&mut markdown, &sample())?, markdown.render(&sample()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_combined_layout_links_to_anchors() {
+        let files =
+            Markdown::new().title("Shop [beta]").layout(MarkdownLayout::Combined).render(&sample());
+        assert_eq!(files.len(), 1);
+        let combined = files[0].contents();
+        assert_eq!(
+            combined,
+            sample().to_markdown().replace("Database Documentation", "Shop \\[beta\\]")
+        );
+        assert!(combined.starts_with("# Shop \\[beta\\]\n"));
+        assert!(combined.contains("- [users](#app-users) — Registered accounts"));
+        assert!(combined.contains("<a id=\"app-users\"></a>\n\n## app.users\n\nRegistered"));
+        assert!(combined.contains("FK → [users.id](#app-users)"));
+        assert!(!combined.contains("[Index]"));
+    }
+
+    #[test]
+    fn test_docs_are_escaped() {
+        let doc = SqlDoc::new(vec![TableDoc::new(
+            None,
+            "users".to_owned(),
+            Some("Registered *accounts*\nOne row per <login>".to_owned()),
+            vec![ColumnDoc::new("id".to_owned(), Some("Key [see `ids`]".to_owned()))],
+            None,
+        )]);
+        let files = Markdown::new().render(&doc);
+        assert!(files[0].contents().contains("- [users](users.md) — Registered \\*accounts\\*\n"));
+        let page = files[1].contents();
+        assert!(page.contains("\n\nRegistered \\*accounts\\*\nOne row per \\<login\\>\n\n"));
+        assert!(page.contains("| `id` |  | yes |  |  | Key \\[see \\`ids\\`\\] |"));
+    }
+
+    #[test]
+    fn test_write_creates_files() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("markdown_write_creates_files");
+        let _ = fs::remove_dir_all(&base);
+        Markdown::new().write(&sample(), &base)?;
+        assert!(fs::read_to_string(base.join("index.md"))?.contains("(app-users.md)"));
+        assert!(fs::read_to_string(base.join("app-users.md"))?.starts_with("# app.users"));
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+}
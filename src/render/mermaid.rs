@@ -0,0 +1,411 @@
+//! Render a [`SqlDoc`] as a Mermaid entity relationship diagram.
+//!
+//! Every table becomes an entity listing its columns with their types and
+//! `PK`/`FK` markers, and every foreign key becomes a relationship whose
+//! cardinality follows the nullability of the referencing column. Column
+//! docs are attached as attribute comments and table docs as `%%` comments.
+//! Large schemas can be narrowed to one schema with [`Mermaid::schema`] or
+//! to the neighbourhood of a table with [`Mermaid::around`].
+
+use core::fmt::{self, Write as _};
+use std::collections::BTreeMap;
+
+use crate::{
+    docs::{ColumnDoc, TableDoc},
+    error::DocError,
+    render::{Reference, Renderer, Sink, references},
+    sql_doc::SqlDoc,
+};
+
+/// The file a [`Mermaid`] diagram is written to through [`Renderer`]
+pub const OUTPUT_FILE: &str = "schema.mmd";
+
+/// Renders a [`SqlDoc`] as a Mermaid `erDiagram`
+///
+/// # Examples
+/// ```
+/// use sql_docs::{GenericDialect, SqlDoc, render::mermaid::Mermaid};
+///
+/// let sql = "CREATE TABLE users (\n  id INTEGER PRIMARY KEY\n);\n\
+///            CREATE TABLE posts (\n  -- Author\n  user_id INTEGER NOT NULL REFERENCES users (id)\n);";
+/// let doc = SqlDoc::builder_from_str(sql).build::<GenericDialect>().unwrap();
+/// let diagram = Mermaid::new().around("posts", 1).render(&doc).unwrap();
+/// assert!(diagram.starts_with("erDiagram\n"));
+/// assert!(diagram.contains("        INTEGER user_id FK \"Author\"\n"));
+/// assert!(diagram.contains("    users ||--o{ posts : \"user_id\"\n"));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Mermaid {
+    schema: Option<String>,
+    focus: Option<(String, usize)>,
+    fenced: bool,
+}
+
+impl Mermaid {
+    /// Creates a [`Mermaid`] renderer drawing every table
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { schema: None, focus: None, fenced: false }
+    }
+
+    /// Only draws the tables in `schema`
+    #[must_use]
+    pub fn schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    /// Only draws `table` and the tables at most `hops` foreign keys away
+    /// from it, in either direction
+    ///
+    /// # Parameters
+    /// - `table`: the name of the table, optionally qualified as `schema.table`
+    /// - `hops`: how many foreign keys to follow; `0` draws the table alone
+    #[must_use]
+    pub fn around(mut self, table: impl Into<String>, hops: usize) -> Self {
+        self.focus = Some((table.into(), hops));
+        self
+    }
+
+    /// Wraps the diagram in a ```` ```mermaid ```` fence, ready to be pasted
+    /// into Markdown
+    #[must_use]
+    pub const fn fenced(mut self, fenced: bool) -> Self {
+        self.fenced = fenced;
+        self
+    }
+
+    /// Renders the selected tables of `doc` as an `erDiagram`
+    ///
+    /// # Parameters
+    /// - `doc`: the [`SqlDoc`] to render
+    ///
+    /// # Errors
+    /// - Returns [`DocError::TableNotFound`] if the table given to
+    ///   [`Mermaid::around`] is not in `doc` (or not in the [`Mermaid::schema`])
+    pub fn render(&self, doc: &SqlDoc) -> Result<String, DocError> {
+        let tables = doc.tables();
+        let references = references(tables);
+        let mut selected: Vec<bool> = tables
+            .iter()
+            .map(|table| self.schema.as_deref().is_none_or(|schema| table.schema() == Some(schema)))
+            .collect();
+        if let Some((name, hops)) = &self.focus {
+            selected = neighbourhood(tables, &references, &selected, name, *hops)?;
+        }
+        Ok(self.diagram(tables, &references, &selected))
+    }
+
+    fn diagram(
+        &self,
+        tables: &[TableDoc],
+        references: &[Reference<'_>],
+        selected: &[bool],
+    ) -> String {
+        let mut out = String::new();
+        let _ = self.write_diagram(&mut out, tables, references, selected);
+        out
+    }
+
+    fn write_diagram(
+        &self,
+        out: &mut String,
+        tables: &[TableDoc],
+        references: &[Reference<'_>],
+        selected: &[bool],
+    ) -> fmt::Result {
+        let ids = entity_ids(tables);
+        if self.fenced {
+            out.push_str("```mermaid\n");
+        }
+        out.push_str("erDiagram\n");
+        for (index, table) in tables.iter().enumerate().filter(|(index, _)| selected[*index]) {
+            write_entity(out, table, &ids[index])?;
+        }
+        for reference in references.iter().filter(|r| selected[r.from] && selected[r.to]) {
+            write_relationship(out, tables, &ids, reference)?;
+        }
+        if self.fenced {
+            out.push_str("```\n");
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Mermaid {
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        sink.file(OUTPUT_FILE).push_str(&self.render(doc)?);
+        Ok(())
+    }
+}
+
+impl SqlDoc {
+    /// Renders every table of this [`SqlDoc`] as a Mermaid `erDiagram`, see
+    /// [`Mermaid`]
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        let references = references(self.tables());
+        Mermaid::new().diagram(self.tables(), &references, &vec![true; self.tables().len()])
+    }
+}
+
+/// Selects the tables in `selected` at most `hops` references away from the
+/// tables named `name`
+fn neighbourhood(
+    tables: &[TableDoc],
+    references: &[Reference<'_>],
+    selected: &[bool],
+    name: &str,
+    hops: usize,
+) -> Result<Vec<bool>, DocError> {
+    let mut reached: Vec<bool> = tables
+        .iter()
+        .zip(selected)
+        .map(|(table, &selected)| {
+            selected && (table.name() == name || table.qualified_name() == name)
+        })
+        .collect();
+    if !reached.contains(&true) {
+        return Err(DocError::TableNotFound { name: name.to_owned() });
+    }
+    let mut frontier = reached.clone();
+    for _ in 0..hops {
+        let mut next = vec![false; tables.len()];
+        for reference in references.iter().filter(|r| selected[r.from] && selected[r.to]) {
+            if frontier[reference.from] && !reached[reference.to] {
+                next[reference.to] = true;
+            }
+            if frontier[reference.to] && !reached[reference.from] {
+                next[reference.from] = true;
+            }
+        }
+        if !next.contains(&true) {
+            break;
+        }
+        for (reached, &next) in reached.iter_mut().zip(&next) {
+            *reached |= next;
+        }
+        frontier = next;
+    }
+    Ok(reached)
+}
+
+fn write_entity(out: &mut String, table: &TableDoc, id: &str) -> fmt::Result {
+    let name = table.qualified_name();
+    if let Some(doc) = table.doc() {
+        writeln!(out, "    %% {name}: {}", one_line(doc))?;
+    }
+    write!(out, "    {id}")?;
+    if *id != name {
+        write!(out, "[\"{}\"]", name.replace('"', "'"))?;
+    }
+    if table.columns().is_empty() {
+        out.push('\n');
+        return Ok(());
+    }
+    out.push_str(" {\n");
+    for column in table.columns_in_order() {
+        write_attribute(out, column)?;
+    }
+    out.push_str("    }\n");
+    Ok(())
+}
+
+fn write_attribute(out: &mut String, column: &ColumnDoc) -> fmt::Result {
+    let data_type = column.data_type().map_or_else(|| "unspecified".to_owned(), word);
+    write!(out, "        {data_type} {}", word(column.name()))?;
+    match (column.is_primary_key(), column.foreign_key().is_some()) {
+        (true, true) => out.push_str(" PK, FK"),
+        (true, false) => out.push_str(" PK"),
+        (false, true) => out.push_str(" FK"),
+        (false, false) => {}
+    }
+    if let Some(doc) = column.doc() {
+        write!(out, " \"{}\"", one_line(doc))?;
+    }
+    out.push('\n');
+    Ok(())
+}
+
+/// Writes `reference` from the referenced table's point of view: exactly one
+/// (or at most one, for a nullable column) referenced row and many referencing
+/// rows, or at most one when the column is the whole primary key
+fn write_relationship(
+    out: &mut String,
+    tables: &[TableDoc],
+    ids: &[String],
+    reference: &Reference<'_>,
+) -> fmt::Result {
+    let column = reference.column;
+    let to = if column.is_nullable() { "|o" } else { "||" };
+    let one_to_one = column.is_primary_key()
+        && tables[reference.from].columns().iter().filter(|c| c.is_primary_key()).count() == 1;
+    let from = if one_to_one { "o|" } else { "o{" };
+    writeln!(
+        out,
+        "    {} {to}--{from} {} : \"{}\"",
+        ids[reference.to],
+        ids[reference.from],
+        column.name().replace('"', "'")
+    )
+}
+
+/// Returns a unique Mermaid identifier for every table in `tables`, keeping
+/// the case of the qualified name; a numeric suffix separates clashes
+fn entity_ids(tables: &[TableDoc]) -> Vec<String> {
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    tables
+        .iter()
+        .map(|table| {
+            let id: String = table
+                .qualified_name()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+                .collect();
+            let count = seen.entry(id.clone()).or_default();
+            *count += 1;
+            if *count == 1 { id } else { format!("{id}_{count}") }
+        })
+        .collect()
+}
+
+/// Turns `text` into a single Mermaid word: whitespace is dropped and
+/// characters Mermaid does not accept in types and names become `_`
+fn word(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| if c.is_ascii_alphanumeric() || "_-()[]".contains(c) { c } else { '_' })
+        .collect()
+}
+
+/// Joins the lines of `doc` with single spaces, replacing the `"` Mermaid
+/// strings cannot hold
+fn one_line(doc: &str) -> String {
+    doc.split_whitespace().collect::<Vec<_>>().join(" ").replace('"', "'")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        error::DocError,
+        render::mermaid::Mermaid,
+        sql_doc::SqlDoc,
+    };
+
+    fn key(table: &str) -> ForeignKey {
+        ForeignKey::new(None, table.to_owned(), Some("id".to_owned()))
+    }
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Registered accounts\nOne row per login".to_owned()),
+            vec![
+                ColumnDoc::new("id".to_owned(), Some("Key".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+                ColumnDoc::new("email".to_owned(), Some("The \"login\"\naddress".to_owned()))
+                    .with_data_type("VARCHAR(255)"),
+            ],
+            None,
+        );
+        let profiles = TableDoc::new(
+            Some("app".to_owned()),
+            "profiles".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("user_id".to_owned(), None)
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true)
+                    .with_foreign_key(key("users")),
+            ],
+            None,
+        );
+        let orders = TableDoc::new(
+            Some("app".to_owned()),
+            "orders".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("user_id".to_owned(), None)
+                    .with_data_type("INTEGER")
+                    .with_foreign_key(key("users")),
+                ColumnDoc::new("total".to_owned(), None).with_data_type("NUMERIC(10, 2)"),
+            ],
+            None,
+        );
+        let lines = TableDoc::new(
+            Some("app".to_owned()),
+            "order_lines".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("order_id".to_owned(), None)
+                    .with_nullable(false)
+                    .with_foreign_key(key("orders")),
+            ],
+            None,
+        );
+        let logs = TableDoc::new(None, "logs".to_owned(), None, vec![], None);
+        SqlDoc::new(vec![users, profiles, orders, lines, logs])
+    }
+
+    #[test]
+    fn test_render_entities_and_relationships() {
+        assert_eq!(
+            sample().to_mermaid(),
+            "erDiagram\n\
+             \x20   logs\n\
+             \x20   app_order_lines[\"app.order_lines\"] {\n\
+             \x20       unspecified order_id FK\n\
+             \x20   }\n\
+             \x20   app_orders[\"app.orders\"] {\n\
+             \x20       INTEGER user_id FK\n\
+             \x20       NUMERIC(10_2) total\n\
+             \x20   }\n\
+             \x20   app_profiles[\"app.profiles\"] {\n\
+             \x20       INTEGER user_id PK, FK\n\
+             \x20   }\n\
+             \x20   %% app.users: Registered accounts One row per login\n\
+             \x20   app_users[\"app.users\"] {\n\
+             \x20       INTEGER id PK \"Key\"\n\
+             \x20       VARCHAR(255) email \"The 'login' address\"\n\
+             \x20   }\n\
+             \x20   app_orders ||--o{ app_order_lines : \"order_id\"\n\
+             \x20   app_users |o--o{ app_orders : \"user_id\"\n\
+             \x20   app_users ||--o| app_profiles : \"user_id\"\n"
+        );
+    }
+
+    #[test]
+    fn test_render_restricted_to_schema_and_hops() -> Result<(), DocError> {
+        let doc = sample();
+        let entities = |diagram: &str| -> Vec<String> {
+            diagram
+                .lines()
+                .filter(|line| line.starts_with("    ") && !line.starts_with("     "))
+                .filter(|line| !line.contains("--") && !line.contains("%%") && line.trim() != "}")
+                .map(|line| line.trim().split(['[', ' ']).next().unwrap_or_default().to_owned())
+                .collect()
+        };
+        let in_app = Mermaid::new().schema("app").render(&doc)?;
+        assert_eq!(
+            entities(&in_app),
+            ["app_order_lines", "app_orders", "app_profiles", "app_users"]
+        );
+        let alone = Mermaid::new().around("app.orders", 0).render(&doc)?;
+        assert_eq!(entities(&alone), ["app_orders"]);
+        assert!(!alone.contains("--"));
+        let one_hop = Mermaid::new().around("orders", 1).render(&doc)?;
+        assert_eq!(entities(&one_hop), ["app_order_lines", "app_orders", "app_users"]);
+        assert!(!one_hop.contains("app_profiles"));
+        let two_hops = Mermaid::new().around("order_lines", 2).fenced(true).render(&doc)?;
+        assert_eq!(entities(&two_hops), ["app_order_lines", "app_orders", "app_users"]);
+        assert!(two_hops.starts_with("```mermaid\nerDiagram\n") && two_hops.ends_with("```\n"));
+        match Mermaid::new().schema("ops").around("users", 1).render(&doc) {
+            Err(DocError::TableNotFound { name }) => assert_eq!(name, "users"),
+            other => panic!("expected TableNotFound, got {other:?}"),
+        }
+        Ok(())
+    }
+}
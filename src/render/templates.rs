@@ -0,0 +1,647 @@
+//! Render a [`SqlDoc`] with user templates.
+//!
+//! Templates use the Jinja-like syntax of [`minijinja`], so a team can
+//! produce `AsciiDoc`, Confluence wiki markup or any other text format without
+//! writing a renderer. Every template sees the same context:
+//!
+//! - `title` — the title given to [`Templates::title`]
+//! - `tables` — every table, ordered by name, see below
+//! - `schemas` — the tables grouped by schema, tables without a schema first,
+//!   as `{ name, tables }` with a `none` name for the default schema
+//! - `source_dialects` — the dialect each source was parsed with, as
+//!   `{ path, dialect, detected }`, when given to [`Templates::source_dialects`]
+//!
+//! A table has `schema`, `name`, `qualified_name`, `doc`, `path`, `line`,
+//! `anchor`, `columns` (in table order) and `referenced_by` (the tables with a
+//! foreign key to it, as `{ qualified_name, anchor }`). A column has `name`,
+//! `doc`, `data_type`, `nullable`, `default`, `primary_key`, `ordinal`,
+//! `anchor` (the table's anchor and the column's, numbered like the column
+//! anchors of the HTML site if two columns slug the same) and `foreign_key`,
+//! which is `none` or holds `schema`, `table`, `column`, `target` (the
+//! referenced qualified name as written) and `anchor` (the referenced table's
+//! anchor, `none` if it is not documented).
+//!
+//! On top of the built-in filters, these helpers are available:
+//!
+//! - `anchor` — turns text into the anchors used above, e.g. `{{ name | anchor }}`
+//! - `escape_markdown`, `escape_asciidoc`, `escape_confluence` — escape the
+//!   characters that are markup in those formats; `escape` covers HTML, and
+//!   templates named `*.html` or `*.xml` are escaped automatically
+//! - `oneline` — joins the lines of a doc with single spaces
+//! - `fk_link(pattern)` — links a column to the table its foreign key
+//!   references by filling `{text}` and `{anchor}` in `pattern`, e.g.
+//!   `{{ column | fk_link("<<{anchor},{text}>>") }}` in `AsciiDoc`; the default
+//!   pattern is the Markdown `[{text}](#{anchor})`. It is empty for columns
+//!   without a foreign key and plain text when the referenced table is not
+//!   documented.
+//!
+//! [`Templates::renderer`] turns a named template into a [`Renderer`], so it
+//! can be [registered](crate::render::Registry::register) next to the
+//! built-in formats.
+
+use std::path::{Path, PathBuf};
+
+use minijinja::{Environment, Error, Value, path_loader};
+use serde::Serialize;
+
+use crate::{
+    ast::SourceDialect,
+    docs::{ColumnDoc, TableDoc},
+    error::DocError,
+    render::{
+        DEFAULT_TITLE, Renderer, Sink, column_slugs, markdown, referenced_table,
+        referencing_tables, slug, table_slugs, tables_by_schema,
+    },
+    sql_doc::SqlDoc,
+};
+
+/// The pattern `fk_link` uses when none is given
+const DEFAULT_LINK: &str = "[{text}](#{anchor})";
+
+#[derive(Serialize)]
+struct Context<'a> {
+    title: &'a str,
+    tables: Vec<TableContext<'a>>,
+    schemas: Vec<SchemaContext<'a>>,
+    source_dialects: Vec<SourceDialectContext<'a>>,
+}
+
+#[derive(Serialize)]
+struct SourceDialectContext<'a> {
+    path: Option<String>,
+    dialect: &'a str,
+    detected: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct SchemaContext<'a> {
+    name: Option<&'a str>,
+    tables: Vec<TableContext<'a>>,
+}
+
+#[derive(Clone, Serialize)]
+struct TableContext<'a> {
+    schema: Option<&'a str>,
+    name: &'a str,
+    qualified_name: String,
+    doc: Option<&'a str>,
+    path: Option<String>,
+    line: Option<u64>,
+    anchor: String,
+    columns: Vec<ColumnContext<'a>>,
+    referenced_by: Vec<TableLink>,
+}
+
+#[derive(Clone, Serialize)]
+struct ColumnContext<'a> {
+    name: &'a str,
+    doc: Option<&'a str>,
+    data_type: Option<&'a str>,
+    nullable: bool,
+    default: Option<&'a str>,
+    primary_key: bool,
+    ordinal: usize,
+    anchor: String,
+    foreign_key: Option<ForeignKeyContext<'a>>,
+}
+
+#[derive(Clone, Serialize)]
+struct ForeignKeyContext<'a> {
+    schema: Option<&'a str>,
+    table: &'a str,
+    column: Option<&'a str>,
+    target: String,
+    anchor: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct TableLink {
+    qualified_name: String,
+    anchor: String,
+}
+
+impl<'a> Context<'a> {
+    fn new(title: &'a str, doc: &'a SqlDoc, dialects: &'a [SourceDialect]) -> Self {
+        let tables = doc.tables();
+        let anchors = table_slugs(tables);
+        let contexts: Vec<TableContext<'a>> =
+            (0..tables.len()).map(|index| TableContext::new(tables, &anchors, index)).collect();
+        let schemas = tables_by_schema(doc)
+            .into_iter()
+            .map(|(name, indexes)| SchemaContext {
+                name,
+                tables: indexes.into_iter().map(|index| contexts[index].clone()).collect(),
+            })
+            .collect();
+        let source_dialects = dialects
+            .iter()
+            .map(|source| SourceDialectContext {
+                path: source.path().map(|path| path.to_string_lossy().replace('\\', "/")),
+                dialect: source.dialect(),
+                detected: source.detected(),
+            })
+            .collect();
+        Self { title, tables: contexts, schemas, source_dialects }
+    }
+}
+
+impl<'a> TableContext<'a> {
+    fn new(tables: &'a [TableDoc], anchors: &[String], index: usize) -> Self {
+        let table = &tables[index];
+        let anchor = &anchors[index];
+        let columns = table
+            .columns_in_order()
+            .into_iter()
+            .zip(column_slugs(table))
+            .map(|(column, column_slug)| {
+                let column_anchor = format!("{anchor}-{column_slug}");
+                ColumnContext::new(tables, table, anchors, column_anchor, column)
+            })
+            .collect();
+        let referenced_by = referencing_tables(tables, index)
+            .into_iter()
+            .map(|i| TableLink {
+                qualified_name: tables[i].qualified_name(),
+                anchor: anchors[i].clone(),
+            })
+            .collect();
+        Self {
+            schema: table.schema(),
+            name: table.name(),
+            qualified_name: table.qualified_name(),
+            doc: table.doc(),
+            path: table.path().map(|path| path.to_string_lossy().replace('\\', "/")),
+            line: table.line(),
+            anchor: anchor.clone(),
+            columns,
+            referenced_by,
+        }
+    }
+}
+
+impl<'a> ColumnContext<'a> {
+    fn new(
+        tables: &'a [TableDoc],
+        table: &'a TableDoc,
+        anchors: &[String],
+        anchor: String,
+        column: &'a ColumnDoc,
+    ) -> Self {
+        let foreign_key = column.foreign_key().map(|key| ForeignKeyContext {
+            schema: key.schema(),
+            table: key.table(),
+            column: key.column(),
+            target: key.to_string(),
+            anchor: referenced_table(tables, table, key).map(|i| anchors[i].clone()),
+        });
+        Self {
+            name: column.name(),
+            doc: column.doc(),
+            data_type: column.data_type(),
+            nullable: column.is_nullable(),
+            default: column.default(),
+            primary_key: column.is_primary_key(),
+            ordinal: column.ordinal(),
+            anchor,
+            foreign_key,
+        }
+    }
+}
+
+/// Renders a [`SqlDoc`] with user templates, see the [module docs](self) for
+/// the context and helpers templates can use
+///
+/// # Examples
+/// ```
+/// use sql_docs::{GenericDialect, SqlDoc, render::templates::Templates};
+///
+/// let sql = "CREATE TABLE users (\n  id INTEGER PRIMARY KEY\n);\n\
+///            CREATE TABLE posts (\n  -- Author\n  user_id INTEGER REFERENCES users (id)\n);";
+/// let doc = SqlDoc::builder_from_str(sql).build::<GenericDialect>().unwrap();
+/// let mut templates = Templates::new().title("Blog");
+/// templates
+///     .add_template(
+///         "doc.adoc",
+///         "= {{ title }}\n{% for table in tables %}\n[[{{ table.anchor }}]]\n== {{ table.name }}\n\
+///          {% for column in table.columns %}\n* `{{ column.name }}` {{ column | fk_link(\"<<{anchor},{text}>>\") }}\
+///          {% endfor %}\n{% endfor %}",
+///     )
+///     .unwrap();
+/// let adoc = templates.render("doc.adoc", &doc).unwrap();
+/// assert!(adoc.starts_with("= Blog\n"));
+/// assert!(adoc.contains("* `user_id` <<users,users.id>>"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Templates {
+    env: Environment<'static>,
+    title: String,
+    source_dialects: Vec<SourceDialect>,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Templates {
+    /// Creates a [`Templates`] renderer with the [`DEFAULT_TITLE`], no
+    /// templates and the helpers described in the [module docs](self)
+    #[must_use]
+    pub fn new() -> Self {
+        let mut env = Environment::new();
+        env.add_filter("anchor", |text: &str| slug(text));
+        env.add_filter("escape_markdown", |text: &str| markdown::escape(text));
+        env.add_filter("escape_asciidoc", escape_asciidoc);
+        env.add_filter("escape_confluence", escape_confluence);
+        env.add_filter("oneline", |text: &str| {
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        });
+        env.add_filter("fk_link", fk_link);
+        Self { env, title: DEFAULT_TITLE.to_owned(), source_dialects: Vec::new() }
+    }
+
+    /// Sets the `title` passed to templates
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the `source_dialects` passed to templates, e.g. as returned by
+    /// [`SqlDocBuilder::build_with_source_dialects`](crate::SqlDocBuilder::build_with_source_dialects)
+    #[must_use]
+    pub fn source_dialects(mut self, dialects: impl IntoIterator<Item = SourceDialect>) -> Self {
+        self.source_dialects = dialects.into_iter().collect();
+        self
+    }
+
+    /// Loads templates that were not added with [`Templates::add_template`]
+    /// from `dir`, by their path relative to it
+    #[must_use]
+    pub fn load_from<P: AsRef<Path> + ?Sized>(mut self, dir: &P) -> Self {
+        self.env.set_loader(path_loader(dir.as_ref()));
+        self
+    }
+
+    /// Adds a template named `name`, replacing any template with that name
+    ///
+    /// # Parameters
+    /// - `name`: the name to render it by; its extension decides whether
+    ///   output is HTML-escaped automatically
+    /// - `source`: the template source
+    ///
+    /// # Errors
+    /// - Returns [`DocError::InvalidTemplate`] if `source` has a syntax error
+    pub fn add_template(
+        &mut self,
+        name: impl Into<String>,
+        source: impl Into<String>,
+    ) -> Result<(), DocError> {
+        self.env.add_template_owned(name.into(), source.into()).map_err(|e| template_error(&e))
+    }
+
+    /// Gives access to the underlying [`Environment`], e.g. to register
+    /// further filters and functions
+    pub const fn environment_mut(&mut self) -> &mut Environment<'static> {
+        &mut self.env
+    }
+
+    /// Renders the template named `name` with `doc` as context
+    ///
+    /// # Errors
+    /// - Returns [`DocError::InvalidTemplate`] if the template cannot be found
+    ///   or loaded, or fails to render
+    pub fn render(&self, name: &str, doc: &SqlDoc) -> Result<String, DocError> {
+        let template = self.env.get_template(name).map_err(|e| template_error(&e))?;
+        template.render(self.context(doc)).map_err(|e| template_error(&e))
+    }
+
+    /// Renders the template `source` with `doc` as context, without adding it
+    ///
+    /// # Errors
+    /// - Returns [`DocError::InvalidTemplate`] if `source` has a syntax error
+    ///   or fails to render
+    pub fn render_str(&self, source: &str, doc: &SqlDoc) -> Result<String, DocError> {
+        self.env.render_str(source, self.context(doc)).map_err(|e| template_error(&e))
+    }
+
+    /// Creates a [`Renderer`] for the template named `name`, e.g. to
+    /// [`register`](crate::render::Registry::register) it as a format; it
+    /// writes one file, named like the template unless
+    /// [`TemplateRenderer::output`] says otherwise
+    #[must_use]
+    pub fn renderer(&self, name: impl Into<String>) -> TemplateRenderer {
+        let template = name.into();
+        TemplateRenderer { templates: self.clone(), output: PathBuf::from(&template), template }
+    }
+
+    fn context<'a>(&'a self, doc: &'a SqlDoc) -> Context<'a> {
+        Context::new(&self.title, doc, &self.source_dialects)
+    }
+}
+
+/// A [`Renderer`] that renders one named template of [`Templates`] into one
+/// file, created by [`Templates::renderer`]
+///
+/// ```
+/// use sql_docs::{
+///     SqlDoc,
+///     docs::TableDoc,
+///     render::{Registry, templates::Templates},
+/// };
+///
+/// let mut templates = Templates::new();
+/// templates
+///     .add_template("tables.txt", "{% for table in tables %}{{ table.name }}\n{% endfor %}")
+///     .unwrap();
+/// let renderer = templates.renderer("tables.txt").output("out/tables.txt");
+/// let mut registry = Registry::new();
+/// registry.register("tables", move || renderer.clone());
+/// let doc = SqlDoc::new(vec![TableDoc::new(None, "users".to_owned(), None, vec![], None)]);
+/// let files = registry.render("tables", &doc).unwrap();
+/// assert_eq!(files[0].contents(), "users\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct TemplateRenderer {
+    templates: Templates,
+    template: String,
+    output: PathBuf,
+}
+
+impl TemplateRenderer {
+    /// Sets the path of the file the template is rendered to
+    #[must_use]
+    pub fn output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output = path.into();
+        self
+    }
+}
+
+impl Renderer for TemplateRenderer {
+    fn object(&mut self, doc: &SqlDoc, sink: &mut Sink) -> Result<(), DocError> {
+        let rendered = self.templates.render(&self.template, doc)?;
+        sink.file(&self.output).push_str(&rendered);
+        Ok(())
+    }
+}
+
+/// Converts a [`minijinja`] error, keeping the template and line it points at
+fn template_error(error: &Error) -> DocError {
+    DocError::InvalidTemplate {
+        name: error.name().map(ToOwned::to_owned),
+        message: error.detail().map_or_else(
+            || error.kind().to_string(),
+            |detail| format!("{}: {detail}", error.kind()),
+        ),
+        line: error.line().map_or(0, |line| line as u64),
+    }
+}
+
+/// Replaces the characters that start `AsciiDoc` inline markup, cell
+/// separators and attribute references with character references, which
+/// `AsciiDoc` renders as the characters themselves
+fn escape_asciidoc(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '&' | '*' | '_' | '`' | '#' | '+' | '^' | '~' | '<' | '>' | '[' | ']' | '{' | '}' | '|'
+        ) {
+            escaped.push_str("&#");
+            escaped.push_str(&u32::from(c).to_string());
+            escaped.push(';');
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Escapes the characters that start Confluence wiki markup with a backslash
+fn escape_confluence(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '{' | '}' | '[' | ']' | '|' | '*' | '_' | '+' | '^' | '~' | '!') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Fills `pattern` with the foreign key of `column`
+#[allow(clippy::literal_string_with_formatting_args)]
+fn fk_link(column: &Value, pattern: Option<&str>) -> Result<String, Error> {
+    let key = column.get_attr("foreign_key")?;
+    if key.is_none() || key.is_undefined() {
+        return Ok(String::new());
+    }
+    let text = key.get_attr("target")?.to_string();
+    let anchor = key.get_attr("anchor")?;
+    if anchor.is_none() || anchor.is_undefined() {
+        return Ok(text);
+    }
+    Ok(pattern
+        .unwrap_or(DEFAULT_LINK)
+        .replace("{anchor}", &anchor.to_string())
+        .replace("{text}", &text))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+    };
+
+    use crate::{
+        ast::SourceDialect,
+        docs::{ColumnDoc, ForeignKey, TableDoc},
+        error::DocError,
+        render::{
+            Registry,
+            templates::{Templates, escape_asciidoc},
+        },
+        sql_doc::SqlDoc,
+    };
+
+    fn sample() -> SqlDoc {
+        let users = TableDoc::new(
+            Some("app".to_owned()),
+            "users".to_owned(),
+            Some("Registered *accounts*\nOne row per login".to_owned()),
+            vec![
+                ColumnDoc::new("id".to_owned(), Some("Key".to_owned()))
+                    .with_data_type("INTEGER")
+                    .with_primary_key(true),
+                ColumnDoc::new("email".to_owned(), Some("Login | contact".to_owned()))
+                    .with_data_type("TEXT"),
+            ],
+            Some(PathBuf::from("sql/users.sql")),
+        )
+        .with_line(3);
+        let orders = TableDoc::new(
+            Some("app".to_owned()),
+            "orders".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("user_id".to_owned(), None)
+                    .with_data_type("INTEGER")
+                    .with_foreign_key(ForeignKey::new(
+                        None,
+                        "users".to_owned(),
+                        Some("id".to_owned()),
+                    )),
+                ColumnDoc::new("sku".to_owned(), None).with_foreign_key(ForeignKey::new(
+                    None,
+                    "products".to_owned(),
+                    None,
+                )),
+            ],
+            None,
+        );
+        let logs = TableDoc::new(None, "logs".to_owned(), None, vec![], None);
+        SqlDoc::new(vec![users, orders, logs])
+    }
+
+    #[test]
+    #[allow(clippy::literal_string_with_formatting_args)]
+    fn test_confluence_template_with_helpers() -> Result<(), DocError> {
+        let source = "\
+{% for schema in schemas %}h1. {{ schema.name or 'Default schema' }}
+{% for table in schema.tables %}{anchor:{{ table.anchor }}}
+h2. {{ table.name }}
+{% if table.doc %}{{ table.doc | oneline | escape_confluence }}
+{% endif %}{% for column in table.columns %}|{{ column.ordinal }}|{{ column.name }}|\
+{{ column.data_type or '' }}|{{ column | fk_link('[{text}|#{anchor}]') }}|
+{% endfor %}{% if table.referenced_by %}Referenced by: \
+{% for t in table.referenced_by %}[{{ t.qualified_name }}|#{{ t.anchor }}]{% endfor %}
+{% endif %}{% endfor %}{% endfor %}";
+        let mut templates = Templates::new();
+        templates.add_template("doc.wiki", source)?;
+        assert_eq!(
+            templates.render("doc.wiki", &sample())?,
+            "h1. Default schema\n{anchor:logs}\nh2. logs\n\
+             h1. app\n{anchor:app-orders}\nh2. orders\n\
+             |1|user_id|INTEGER|[users.id|#app-users]|\n|2|sku||products|\n\
+             {anchor:app-users}\nh2. users\nRegistered \\*accounts\\* One row per login\n\
+             |1|id|INTEGER||\n|2|email|TEXT||\n\
+             Referenced by: [app.orders|#app-orders]\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_asciidoc() -> Result<(), DocError> {
+        assert_eq!(
+            escape_asciidoc("*bold* _it_ `code` #mark# a|b <<xref>> [[id]] {attr} R&D"),
+            "&#42;bold&#42; &#95;it&#95; &#96;code&#96; &#35;mark&#35; a&#124;b \
+             &#60;&#60;xref&#62;&#62; &#91;&#91;id&#93;&#93; &#123;attr&#125; R&#38;D"
+        );
+        assert_eq!(escape_asciidoc("Plain text, 100% safe."), "Plain text, 100% safe.");
+        let templates = Templates::new();
+        assert_eq!(
+            templates.render_str("{{ tables[2].doc | escape_asciidoc }}", &sample())?,
+            "Registered &#42;accounts&#42;\nOne row per login"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_str_context_and_escaping() -> Result<(), DocError> {
+        let templates = Templates::new().title("Shop");
+        let doc = sample();
+        let users = "{% set t = tables[2] %}{{ title }}: {{ t.qualified_name }} \
+                     ({{ t.path }}:{{ t.line }}) {{ t.columns[1].anchor }} \
+                     {{ t.columns[1].doc | escape_markdown }} {{ 'A B.c' | anchor }}";
+        assert_eq!(
+            templates.render_str(users, &doc)?,
+            "Shop: app.users (sql/users.sql:3) app-users-email Login \\| contact a-b-c"
+        );
+        let markdown = "{{ tables[1].columns[0] | fk_link }}";
+        assert_eq!(templates.render_str(markdown, &doc)?, "[users.id](#app-users)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_anchors_and_source_dialects() -> Result<(), DocError> {
+        let doc = SqlDoc::new(vec![TableDoc::new(
+            None,
+            "events".to_owned(),
+            None,
+            vec![
+                ColumnDoc::new("created at".to_owned(), None),
+                ColumnDoc::new("created-at".to_owned(), None),
+            ],
+            None,
+        )]);
+        let templates = Templates::new().source_dialects([
+            SourceDialect::new(Some(PathBuf::from("sql/events.sql")), "postgres".to_owned(), true),
+            SourceDialect::new(None, "mysql".to_owned(), false),
+        ]);
+        let source = "{% for c in tables[0].columns %}{{ c.anchor }} {% endfor %}\
+                      {% for s in source_dialects %}{{ s.path }}={{ s.dialect }}/{{ s.detected }} {% endfor %}";
+        assert_eq!(
+            templates.render_str(source, &doc)?,
+            "events-created-at events-created-at-2 sql/events.sql=postgres/True None=mysql/False "
+        );
+        assert_eq!(Templates::new().render_str("{{ source_dialects | length }}", &doc)?, "0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_errors_and_directory_loading() -> Result<(), Box<dyn std::error::Error>> {
+        let mut templates = Templates::new();
+        match templates.add_template("broken.txt", "line\n{% for %}") {
+            Err(DocError::InvalidTemplate { name, line, .. }) => {
+                assert_eq!(name.as_deref(), Some("broken.txt"));
+                assert_eq!(line, 2);
+            }
+            other => panic!("expected InvalidTemplate, got {other:?}"),
+        }
+        assert!(matches!(
+            templates.render("missing.txt", &sample()),
+            Err(DocError::InvalidTemplate { .. })
+        ));
+
+        let base = env::temp_dir().join("templates_directory_loading");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        fs::write(base.join("title.html"), "<h1>{{ title }}</h1>")?;
+        fs::write(base.join("title.txt"), "<h1>{{ title }}</h1>")?;
+        let templates = Templates::new().title("R&D <db>").load_from(&base);
+        assert_eq!(templates.render("title.html", &sample())?, "<h1>R&amp;D &lt;db&gt;</h1>");
+        assert_eq!(templates.render("title.txt", &sample())?, "<h1>R&D <db></h1>");
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_renderer_in_a_registry() -> Result<(), DocError> {
+        let mut templates = Templates::new().title("Shop");
+        templates.add_template(
+            "doc.txt",
+            "{{ title }}:{% for table in tables %} {{ table.name }}{% endfor %}",
+        )?;
+        let named = templates.renderer("doc.txt");
+        let moved = templates.renderer("doc.txt").output("out/shop.txt");
+        let missing = templates.renderer("missing.txt");
+        let mut registry = Registry::new();
+        registry.register("named", move || named.clone());
+        registry.register("moved", move || moved.clone());
+        registry.register("missing", move || missing.clone());
+
+        let files = registry.render("named", &sample())?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path(), Path::new("doc.txt"));
+        assert_eq!(files[0].contents(), "Shop: logs orders users");
+        let files = registry.render("moved", &sample())?;
+        assert_eq!(files[0].path(), Path::new("out/shop.txt"));
+        assert!(matches!(
+            registry.render("missing", &sample()),
+            Err(DocError::InvalidTemplate { .. })
+        ));
+        Ok(())
+    }
+}
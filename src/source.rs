@@ -1,5 +1,10 @@
 //! Module for structuring the Sql input
-use crate::files::{SqlContent, SqlContentSet};
+use crate::{
+    comments::dialect_directive,
+    encoding::{self, Encoding},
+    files::{SqlContent, SqlContentSet, decompress_for},
+    provider::SourceProvider,
+};
 use std::{
     io,
     path::{Path, PathBuf},
@@ -18,11 +23,63 @@ impl SqlSource {
     /// # Errors
     /// - Returns an [`io::Error`] if the file cannot be read.
     pub fn from_path(path: &Path) -> io::Result<Self> {
-        let source = SqlContent::from_path(path)?;
+        Self::from_path_with_encoding(path, None)
+    }
+
+    /// Loads a [`SqlSource`] from the given path, decoding it with an explicit
+    /// [`Encoding`] when one is given instead of detecting it.
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the file cannot be read or is not valid in
+    ///   its encoding.
+    pub fn from_path_with_encoding(path: &Path, encoding: Option<Encoding>) -> io::Result<Self> {
+        let source = SqlContent::from_path_with_encoding(path, encoding)?;
         let content = source.content().to_owned();
         Ok(Self { path: Some(path.to_owned()), content })
     }
 
+    /// Loads the file at `path` from a [`SourceProvider`], decompressing and
+    /// decoding it like [`SqlSource::from_path_with_encoding`].
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if the provider cannot read the file or it
+    ///   is not valid in its encoding.
+    pub fn from_provider(
+        provider: &dyn SourceProvider,
+        path: &Path,
+        encoding: Option<Encoding>,
+    ) -> io::Result<Self> {
+        let bytes = decompress_for(path, provider.read(path)?)?;
+        let content = encoding::decode(&bytes, encoding)?;
+        Ok(Self { path: Some(path.to_owned()), content })
+    }
+
+    /// Reads an [`SqlSource`] from any [`io::Read`], such as standard input,
+    /// decompressing and decoding it like [`SqlSource::from_path_with_encoding`]
+    /// would for a file at `path`.
+    ///
+    /// # Parameters
+    /// - `reader`: the reader to consume
+    /// - `path`: the virtual path reported for the source, if any
+    /// - `encoding`: the encoding of the content, or `None` to detect it
+    ///
+    /// # Errors
+    /// - Returns an [`io::Error`] if reading fails or the content is not valid
+    ///   in its encoding.
+    pub fn from_reader<R: io::Read>(
+        mut reader: R,
+        path: Option<PathBuf>,
+        encoding: Option<Encoding>,
+    ) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if let Some(path) = &path {
+            bytes = decompress_for(path, bytes)?;
+        }
+        let content = encoding::decode(&bytes, encoding)?;
+        Ok(Self { path, content })
+    }
+
     /// Creates an [`SqlSource`] from a a [`String`] and a [`Option<PathBuf>`]
     #[must_use]
     pub const fn from_str(content: String, path: Option<PathBuf>) -> Self {
@@ -46,6 +103,31 @@ impl SqlSource {
         &self.content
     }
 
+    /// Returns the dialect named by a `-- dialect: <name>` line in the file
+    /// header, i.e. among the single line comments before any SQL.
+    ///
+    /// ```
+    /// use sql_docs::source::SqlSource;
+    ///
+    /// let source = SqlSource::from_str("-- dialect: bigquery\nSELECT 1;".to_owned(), None);
+    /// assert_eq!(source.dialect_directive(), Some("bigquery"));
+    /// ```
+    #[must_use]
+    pub fn dialect_directive(&self) -> Option<&str> {
+        self.header_dialect_directive().map(|(_, name)| name)
+    }
+
+    /// Returns the 1-based line of the directive read by
+    /// [`SqlSource::dialect_directive`] along with the dialect name
+    pub(crate) fn header_dialect_directive(&self) -> Option<(u64, &str)> {
+        self.content
+            .lines()
+            .map(str::trim)
+            .zip(1..)
+            .take_while(|(line, _)| line.is_empty() || line.starts_with("--"))
+            .find_map(|(line, number)| Some((number, dialect_directive(line.strip_prefix("--")?)?)))
+    }
+
     /// Recursively discovers `.sql` files under `path`, applies the optional
     /// deny list, and loads the contents of each file.
     ///
@@ -117,4 +199,19 @@ mod tests {
         assert!(file.path_into_path_buf().is_none());
         assert_eq!(file.content(), sql);
     }
+
+    #[test]
+    fn test_dialect_directive_only_read_from_header() {
+        let header = "\n-- Warehouse tables\n--  Dialect:  bigquery \nCREATE TABLE t (id INT64);";
+        assert_eq!(
+            SqlSource::from_str(header.to_owned(), None).dialect_directive(),
+            Some("bigquery")
+        );
+
+        let late = "CREATE TABLE t (id INT);\n-- dialect: mysql\n";
+        assert_eq!(SqlSource::from_str(late.to_owned(), None).dialect_directive(), None);
+
+        let empty = "-- dialect:\nCREATE TABLE t (id INT);";
+        assert_eq!(SqlSource::from_str(empty.to_owned(), None).dialect_directive(), None);
+    }
 }
@@ -1,28 +1,81 @@
 //! Public entry point for building [`SqlDoc`] from a directory, file, or string.
 
 use std::{
+    fmt, io,
     path::{Path, PathBuf},
-    vec,
+    ptr, slice, vec,
 };
 
-use sqlparser::dialect::Dialect;
+use sqlparser::{
+    dialect::{Dialect, GenericDialect},
+    parser::ParserError,
+};
 
 use crate::{
-    ast::ParsedSqlFile,
+    ast::{ParsedSqlFile, SourceDialect, dialect_from_name, parse_error_span},
     comments::{Comments, LeadingCommentCapture, MultiFlatten},
     docs::{SqlFileDoc, TableDoc},
+    encoding::Encoding,
     error::DocError,
-    files::SqlFiles,
+    files::{DenyList, GlobPattern, ScanOptions},
+    provider::{FsProvider, SourceProvider},
     source::SqlSource,
+    warnings::DocWarning,
 };
 
+/// The docs generated for one source together with the warnings raised for it
+/// and, when detecting dialects, the dialect it was parsed with
+type FileDocs = (SqlFileDoc, Vec<DocWarning>, Option<SourceDialect>);
+
 /// Top-level documentation object containing all discovered [`TableDoc`] entries.
+///
+/// With the `serde` feature, it serializes as an object holding the
+/// `SERDE_FORMAT_VERSION` next to its `tables`, and refuses to deserialize
+/// data written by a newer format version.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "SqlDocRepr", try_from = "SqlDocRepr"))]
 pub struct SqlDoc {
     /// Holds the [`Vec`] of all tables found in all specified files.
     tables: Vec<TableDoc>,
 }
 
+/// The version of the serialized form of [`SqlDoc`], increased whenever that
+/// form changes incompatibly
+#[cfg(feature = "serde")]
+pub const SERDE_FORMAT_VERSION: u32 = 1;
+
+/// The serialized form of [`SqlDoc`]
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SqlDocRepr {
+    format_version: u32,
+    tables: Vec<TableDoc>,
+}
+
+#[cfg(feature = "serde")]
+impl From<SqlDoc> for SqlDocRepr {
+    fn from(doc: SqlDoc) -> Self {
+        Self { format_version: SERDE_FORMAT_VERSION, tables: doc.tables }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SqlDocRepr> for SqlDoc {
+    type Error = String;
+
+    fn try_from(repr: SqlDocRepr) -> Result<Self, Self::Error> {
+        if repr.format_version > SERDE_FORMAT_VERSION {
+            return Err(format!(
+                "sql_docs format version {} is newer than the supported version \
+                 {SERDE_FORMAT_VERSION}",
+                repr.format_version
+            ));
+        }
+        Ok(Self::new(repr.tables))
+    }
+}
+
 impl SqlDoc {
     /// Method for creating a new [`SqlDoc`]
     #[must_use]
@@ -54,12 +107,7 @@ impl SqlDoc {
     /// assert_eq!(users.name(), "users");
     /// ```
     pub fn from_dir<P: AsRef<Path> + ?Sized>(root: &P) -> SqlDocBuilder<'_> {
-        SqlDocBuilder {
-            source: SqlFileDocSource::Dir(root.as_ref().to_path_buf()),
-            deny: Vec::new(),
-            multiline_flat: MultiFlatten::default(),
-            leading_type: LeadingCommentCapture::default(),
-        }
+        SqlDocBuilder::new().add_dir(root)
     }
 
     /// Creates an [`SqlDocBuilder`] from a single SQL file on disk.
@@ -82,12 +130,7 @@ impl SqlDoc {
     /// assert_eq!(t.name(), "users");
     /// ```
     pub fn from_path<P: AsRef<Path> + ?Sized>(path: &P) -> SqlDocBuilder<'_> {
-        SqlDocBuilder {
-            source: SqlFileDocSource::File(path.as_ref().to_path_buf()),
-            deny: Vec::new(),
-            multiline_flat: MultiFlatten::default(),
-            leading_type: LeadingCommentCapture::default(),
-        }
+        SqlDocBuilder::new().add_path(path)
     }
 
     /// Creates an [`SqlDocBuilder`] from an explicit list of SQL file paths.
@@ -112,14 +155,79 @@ impl SqlDoc {
     /// assert!(doc.table("posts", None).is_ok());
     /// ```
     pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> SqlDocBuilder<'_> {
-        SqlDocBuilder {
-            source: SqlFileDocSource::Files(
-                paths.iter().map(|p| p.as_ref().to_path_buf()).collect(),
-            ),
-            deny: Vec::new(),
-            multiline_flat: MultiFlatten::default(),
-            leading_type: LeadingCommentCapture::default(),
-        }
+        SqlDocBuilder::new().add_paths(paths)
+    }
+
+    /// Creates an [`SqlDocBuilder`] that documents the files of a
+    /// [`SourceProvider`], such as an in-memory map or an archive.
+    ///
+    /// Files are listed with the builder's scan settings, so
+    /// [`SqlDocBuilder::include`], [`SqlDocBuilder::exclude`] and
+    /// [`SqlDocBuilder::extensions`] apply as they do for [`SqlDoc::from_dir`].
+    ///
+    /// # Parameters
+    /// - `provider`: the [`SourceProvider`] to list and read files from
+    ///
+    /// # Examples
+    /// ```
+    /// use sql_docs::{SqlDoc, GenericDialect, provider::MemoryProvider};
+    ///
+    /// let files = MemoryProvider::new()
+    ///     .with_file("app/users.sql", "CREATE TABLE users (id INTEGER);")
+    ///     .with_file("legacy/posts.sql", "CREATE TABLE posts (id INTEGER);");
+    /// let doc = SqlDoc::from_provider(&files)
+    ///     .exclude("legacy/**")
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// assert_eq!(doc.number_of_tables(), 1);
+    /// ```
+    #[must_use]
+    pub fn from_provider(provider: &dyn SourceProvider) -> SqlDocBuilder<'_> {
+        SqlDocBuilder::new().add_provider(provider)
+    }
+
+    /// Creates an [`SqlDocBuilder`] from SQL read from any [`io::Read`], such as
+    /// the output of `pg_dump --schema-only` piped into your tool.
+    ///
+    /// The reader is consumed when the builder is built. A `.gz` or `.zst`
+    /// virtual path makes the content be decompressed first, as for files.
+    ///
+    /// # Parameters
+    /// - `reader`: the reader to take the SQL from
+    /// - `path`: the virtual path reported for its tables and in error spans, if any
+    ///
+    /// # Examples
+    /// ```
+    /// use std::path::Path;
+    /// use sql_docs::{SqlDoc, GenericDialect};
+    ///
+    /// let dump: &[u8] = b"-- Accounts\nCREATE TABLE users (id INTEGER);";
+    /// let doc = SqlDoc::from_reader(dump, Some(Path::new("dump.sql")))
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// let users = doc.table("users", None).unwrap();
+    /// assert_eq!(users.path(), Some(Path::new("dump.sql")));
+    /// ```
+    #[must_use]
+    pub fn from_reader<'a, R: io::Read + 'a>(reader: R, path: Option<&Path>) -> SqlDocBuilder<'a> {
+        SqlDocBuilder::new().add_reader(reader, path)
+    }
+
+    /// Creates an [`SqlDocBuilder`] from SQL read from standard input, so that
+    /// `pg_dump --schema-only mydb | my-tool` works without a temporary file.
+    ///
+    /// Tables have no path; use [`SqlDoc::from_reader`] with [`io::stdin`] to
+    /// report a virtual path instead.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sql_docs::{SqlDoc, PostgreSqlDialect};
+    ///
+    /// let doc = SqlDoc::from_stdin().build::<PostgreSqlDialect>().unwrap();
+    /// ```
+    #[must_use]
+    pub fn from_stdin() -> SqlDocBuilder<'static> {
+        SqlDocBuilder::new().add_reader(io::stdin(), None)
     }
 
     /// Creates an [`SqlDocBuilder`] from raw SQL text.
@@ -152,12 +260,7 @@ impl SqlDoc {
     /// ```
     #[must_use]
     pub fn builder_from_str(content: &str) -> SqlDocBuilder<'_> {
-        SqlDocBuilder {
-            source: SqlFileDocSource::FromString(content),
-            deny: Vec::new(),
-            multiline_flat: MultiFlatten::default(),
-            leading_type: LeadingCommentCapture::default(),
-        }
+        SqlDocBuilder::new().add_str(content)
     }
 
     /// Creates an [`SqlDocBuilder`] from from raw SQL text while preserving an associated path.
@@ -196,12 +299,32 @@ impl SqlDoc {
     pub fn builder_from_strs_with_paths(
         string_with_path: &[(String, PathBuf)],
     ) -> SqlDocBuilder<'_> {
-        SqlDocBuilder {
-            source: SqlFileDocSource::FromStringsWithPaths(string_with_path),
-            deny: Vec::new(),
-            multiline_flat: MultiFlatten::default(),
-            leading_type: LeadingCommentCapture::default(),
-        }
+        SqlDocBuilder::new().add_strs_with_paths(string_with_path)
+    }
+
+    /// Creates an [`SqlDocBuilder`] without any source, to combine several
+    /// directories, files, strings and providers in one [`SqlDoc`].
+    ///
+    /// Sources are added with [`SqlDocBuilder::add_dir`],
+    /// [`SqlDocBuilder::add_path`], [`SqlDocBuilder::add_str`] and their
+    /// siblings, and are documented in the order they were added.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sql_docs::{SqlDoc, GenericDialect};
+    ///
+    /// let generated = "CREATE TABLE audit_log (id INTEGER);".to_owned();
+    /// let doc = SqlDoc::builder()
+    ///     .add_dir("schema")
+    ///     .deny("schema/legacy.sql")
+    ///     .add_paths(&["extra/views.sql", "extra/roles.sql"])
+    ///     .add_str(&generated)
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub const fn builder<'a>() -> SqlDocBuilder<'a> {
+        SqlDocBuilder::new()
     }
 
     /// Method for finding a specific [`TableDoc`] by `name`
@@ -272,34 +395,297 @@ impl SqlDoc {
 /// Builder structure for the [`SqlDoc`]
 #[derive(Debug, Eq, PartialEq)]
 pub struct SqlDocBuilder<'a> {
-    /// The source for implementing the [`SqlDoc`] to be built
-    source: SqlFileDocSource<'a>,
-    /// The list of Paths to be ignored for parsing purposes.
+    /// The sources for implementing the [`SqlDoc`] to be built, in the order added
+    sources: Vec<SourceSpec<'a>>,
+    /// The list of Paths to be ignored in every source, added before any source
     deny: Vec<String>,
+    /// Include and exclude patterns applied when scanning a directory
+    scan: ScanOptions,
     /// Tracks the chosen setting for flattening multiline comments
     multiline_flat: MultiFlatten<'a>,
     /// Tracks the chosen setting for leading comment collection
     leading_type: LeadingCommentCapture,
+    /// Whether to keep building after a file fails and report every error at the end
+    collect_errors: bool,
+    /// Path globs and the names of the dialects used for files matching them
+    dialect_overrides: Vec<(String, String)>,
+    /// Whether to detect the dialect of files without a header or override
+    detect_dialect: bool,
+    /// The encoding of sources without their own, set before any source
+    encoding: Option<Encoding>,
 }
 
-/// Enum for specifying a file doc source as a `directory` or a specific `file`
+/// One source of a build together with the paths denied within it and its encoding
 #[derive(Debug, Eq, PartialEq)]
+struct SourceSpec<'a> {
+    source: SqlFileDocSource<'a>,
+    /// The list of Paths of this source to be ignored for parsing purposes.
+    deny: Vec<String>,
+    /// The encoding of this source, detected from a byte order mark when `None`
+    encoding: Option<Encoding>,
+}
+
+/// Enum for specifying a file doc source as a `directory` or a specific `file`
+#[derive(Debug)]
 enum SqlFileDocSource<'a> {
     Dir(PathBuf),
     File(PathBuf),
     Files(Vec<PathBuf>),
     FromString(&'a str),
     FromStringsWithPaths(&'a [(String, PathBuf)]),
+    Provider(&'a dyn SourceProvider),
+    Reader(ReaderSource<'a>),
+}
+
+/// A reader consumed when the builder is built, with the virtual path its
+/// tables and errors are reported under
+struct ReaderSource<'a> {
+    reader: Box<dyn io::Read + 'a>,
+    path: Option<PathBuf>,
+}
+
+impl fmt::Debug for ReaderSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReaderSource").field("path", &self.path).finish_non_exhaustive()
+    }
 }
 
+/// Providers and readers are compared by identity, as they need not be comparable
+impl PartialEq for SqlFileDocSource<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Dir(a), Self::Dir(b)) | (Self::File(a), Self::File(b)) => a == b,
+            (Self::Files(a), Self::Files(b)) => a == b,
+            (Self::FromString(a), Self::FromString(b)) => a == b,
+            (Self::FromStringsWithPaths(a), Self::FromStringsWithPaths(b)) => a == b,
+            (Self::Provider(a), Self::Provider(b)) => ptr::addr_eq(*a, *b),
+            (Self::Reader(a), Self::Reader(b)) => {
+                ptr::addr_eq(&raw const *a.reader, &raw const *b.reader)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SqlFileDocSource<'_> {}
+
 impl<'a> SqlDocBuilder<'a> {
+    /// Creates a builder without sources and with every setting at its default
+    const fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            deny: Vec::new(),
+            scan: ScanOptions::new(),
+            multiline_flat: MultiFlatten::NoFlat,
+            leading_type: LeadingCommentCapture::SingleNearest,
+            collect_errors: false,
+            dialect_overrides: Vec::new(),
+            detect_dialect: false,
+            encoding: None,
+        }
+    }
+
     /// Method for adding an item to the deny list
     ///
+    /// The path is denied within the source added last, so each source can
+    /// have its own deny rules; if no source was added yet it is denied in
+    /// every source.
+    ///
     /// # Parameters
     /// - The `path` that will be added to deny path `Vec`
     #[must_use]
     pub fn deny(mut self, deny_path: &str) -> Self {
-        self.deny.push(deny_path.into());
+        let deny = self.sources.last_mut().map_or(&mut self.deny, |spec| &mut spec.deny);
+        deny.push(deny_path.into());
+        self
+    }
+
+    /// Adds a directory to scan for SQL files, see [`SqlDoc::from_dir`]
+    ///
+    /// # Parameters
+    /// - `root`: Path to the directory containing SQL files.
+    #[must_use]
+    pub fn add_dir<P: AsRef<Path> + ?Sized>(self, root: &P) -> Self {
+        self.add(SqlFileDocSource::Dir(root.as_ref().to_path_buf()))
+    }
+
+    /// Adds a single SQL file, see [`SqlDoc::from_path`]
+    ///
+    /// # Parameters
+    /// - `path`: Path to a single SQL file.
+    #[must_use]
+    pub fn add_path<P: AsRef<Path> + ?Sized>(self, path: &P) -> Self {
+        self.add(SqlFileDocSource::File(path.as_ref().to_path_buf()))
+    }
+
+    /// Adds a list of SQL files, see [`SqlDoc::from_paths`]
+    ///
+    /// # Parameters
+    /// - `paths`: Slice of paths to SQL files.
+    #[must_use]
+    pub fn add_paths<P: AsRef<Path>>(self, paths: &[P]) -> Self {
+        self.add(SqlFileDocSource::Files(paths.iter().map(|p| p.as_ref().to_path_buf()).collect()))
+    }
+
+    /// Adds raw SQL text without a path, see [`SqlDoc::builder_from_str`]
+    ///
+    /// # Parameters
+    /// - `content`: SQL text to parse.
+    #[must_use]
+    pub fn add_str(self, content: &'a str) -> Self {
+        self.add(SqlFileDocSource::FromString(content))
+    }
+
+    /// Adds raw SQL texts with their paths, see [`SqlDoc::builder_from_strs_with_paths`]
+    ///
+    /// # Parameters
+    /// - `string_with_path`: Slice of `(sql, path)` pairs.
+    #[must_use]
+    pub fn add_strs_with_paths(self, string_with_path: &'a [(String, PathBuf)]) -> Self {
+        self.add(SqlFileDocSource::FromStringsWithPaths(string_with_path))
+    }
+
+    /// Adds the files of a [`SourceProvider`], see [`SqlDoc::from_provider`]
+    ///
+    /// # Parameters
+    /// - `provider`: the [`SourceProvider`] to list and read files from
+    #[must_use]
+    pub fn add_provider(self, provider: &'a dyn SourceProvider) -> Self {
+        self.add(SqlFileDocSource::Provider(provider))
+    }
+
+    /// Adds SQL read from `reader` when the builder is built, see
+    /// [`SqlDoc::from_reader`]
+    ///
+    /// # Parameters
+    /// - `reader`: any [`io::Read`], e.g. a pipe or [`io::stdin`]
+    /// - `path`: the virtual path reported for its tables and errors, if any
+    #[must_use]
+    pub fn add_reader<R: io::Read + 'a>(self, reader: R, path: Option<&Path>) -> Self {
+        self.add(SqlFileDocSource::Reader(ReaderSource {
+            reader: Box::new(reader),
+            path: path.map(Path::to_path_buf),
+        }))
+    }
+
+    /// Appends `source` without any deny rules or encoding of its own
+    fn add(mut self, source: SqlFileDocSource<'a>) -> Self {
+        self.sources.push(SourceSpec { source, deny: Vec::new(), encoding: None });
+        self
+    }
+
+    /// Only documents files matching `pattern` when scanning a directory.
+    ///
+    /// Patterns are [`GlobPattern`]s matched against the path relative to the
+    /// directory passed to [`SqlDoc::from_dir`], anchored to it when they
+    /// contain a `/` (see [`ScanOptions`]); a leading `!` turns the pattern
+    /// into an exclude, as with [`SqlDocBuilder::exclude`].
+    ///
+    /// # Parameters
+    /// - `pattern`: the glob, e.g. `"**/seed/*.sql"`
+    #[must_use]
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.scan = self.scan.pattern(pattern);
+        self
+    }
+
+    /// Decodes files with `encoding` instead of detecting it from a byte order
+    /// mark and falling back to UTF-8.
+    ///
+    /// Like [`SqlDocBuilder::deny`], the encoding applies to the source added
+    /// last, so each source can have its own; if no source was added yet it
+    /// applies to every source without one.
+    ///
+    /// # Parameters
+    /// - `encoding`: the [`Encoding`] of the files of the source
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sql_docs::{Encoding, SqlDoc, GenericDialect};
+    ///
+    /// let doc = SqlDoc::from_dir("oracle_scripts")
+    ///     .encoding(Encoding::Latin1)
+    ///     .add_dir("ssms_exports")
+    ///     .encoding(Encoding::Utf16Le)
+    ///     .add_dir("schema")
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        let target = self.sources.last_mut().map_or(&mut self.encoding, |spec| &mut spec.encoding);
+        *target = Some(encoding);
+        self
+    }
+
+    /// Sets the file extensions looked for when scanning a directory,
+    /// replacing the default of `sql`.
+    ///
+    /// # Parameters
+    /// - `extensions`: extensions without the leading dot, e.g. `["sql", "ddl", "pgsql", "psql"]`
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sql_docs::{SqlDoc, GenericDialect};
+    ///
+    /// let doc = SqlDoc::from_dir("dumps")
+    ///     .extensions(&["sql", "ddl", "pgsql", "psql"])
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: &[S]) -> Self {
+        self.scan = self.scan.extensions(extensions);
+        self
+    }
+
+    /// Replaces the [`ScanOptions`] used when scanning a directory, e.g. to
+    /// stop honoring `.gitignore` files or to limit the scan depth.
+    ///
+    /// This also replaces any patterns added with [`SqlDocBuilder::include`]
+    /// and [`SqlDocBuilder::exclude`], so call it before them.
+    ///
+    /// # Parameters
+    /// - `options`: the [`ScanOptions`] to use
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sql_docs::{SqlDoc, GenericDialect, files::ScanOptions};
+    ///
+    /// let doc = SqlDoc::from_dir("sql")
+    ///     .scan_options(ScanOptions::new().include_hidden(true).max_depth(2))
+    ///     .exclude("**/archive/**")
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn scan_options(mut self, options: ScanOptions) -> Self {
+        self.scan = options;
+        self
+    }
+
+    /// Skips files matching `pattern` when scanning a directory.
+    ///
+    /// Unlike [`SqlDocBuilder::deny`], the pattern is relative to the scanned
+    /// directory, so it keeps working from any working directory and can
+    /// exclude whole folders.
+    ///
+    /// # Parameters
+    /// - `pattern`: the glob, e.g. `"**/archive/**"`
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sql_docs::{SqlDoc, GenericDialect};
+    ///
+    /// let doc = SqlDoc::from_dir("migrations")
+    ///     .include("**/seed/*.sql")
+    ///     .exclude("**/archive/**")
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.scan = self.scan.exclude(pattern);
         self
     }
 
@@ -344,6 +730,73 @@ impl<'a> SqlDocBuilder<'a> {
         self
     }
 
+    /// Keeps building after a file fails so that every failing file is reported.
+    ///
+    /// By default [`SqlDocBuilder::build`] stops at the first error. With this
+    /// setting every file is processed and, if any of them failed, the errors
+    /// are returned together as [`DocError::MultipleErrors`].
+    #[must_use]
+    pub const fn collect_all_errors(mut self) -> Self {
+        self.collect_errors = true;
+        self
+    }
+
+    /// Parses files whose path matches `pattern` with the dialect named
+    /// `dialect_name` instead of the dialect passed to the build.
+    ///
+    /// Patterns are [`GlobPattern`]s matched against the path relative to the
+    /// scanned directory, or against the path as given for other sources. When
+    /// several patterns match, the last one added wins. A `-- dialect: <name>`
+    /// line in a file's header takes precedence over every pattern.
+    ///
+    /// # Parameters
+    /// - `pattern`: the glob selecting the files, e.g. `"warehouse/**"`
+    /// - `dialect_name`: a name accepted by [`crate::ast::dialect_from_name`]
+    ///
+    /// # Examples
+    /// ```
+    /// use std::path::PathBuf;
+    /// use sql_docs::{SqlDoc, GenericDialect};
+    ///
+    /// let inputs = vec![
+    ///     ("CREATE TABLE a (id INTEGER);".to_owned(), PathBuf::from("app/a.sql")),
+    ///     ("CREATE TABLE b (id INT64);".to_owned(), PathBuf::from("warehouse/b.sql")),
+    /// ];
+    /// let doc = SqlDoc::builder_from_strs_with_paths(&inputs)
+    ///     .dialect_for("warehouse/**", "bigquery")
+    ///     .build::<GenericDialect>()
+    ///     .unwrap();
+    /// assert_eq!(doc.number_of_tables(), 2);
+    /// ```
+    #[must_use]
+    pub fn dialect_for(mut self, pattern: &str, dialect_name: &str) -> Self {
+        self.dialect_overrides.push((pattern.to_owned(), dialect_name.to_owned()));
+        self
+    }
+
+    /// Detects the dialect of each file instead of using the dialect passed
+    /// to the build, see [`ParsedSqlFile::parse_detecting_dialect`].
+    ///
+    /// A `-- dialect:` header or a [`SqlDocBuilder::dialect_for`] override still
+    /// takes precedence. The dialect used for every file is reported by
+    /// [`SqlDocBuilder::build_with_source_dialects`].
+    ///
+    /// # Examples
+    /// ```
+    /// use sql_docs::{SqlDoc, GenericDialect};
+    ///
+    /// let (_, source_dialects) = SqlDoc::builder_from_str("CREATE TABLE `t` (id INT AUTO_INCREMENT);")
+    ///     .detect_dialect()
+    ///     .build_with_source_dialects::<GenericDialect>()
+    ///     .unwrap();
+    /// assert_eq!(source_dialects[0].dialect(), "mysql");
+    /// ```
+    #[must_use]
+    pub const fn detect_dialect(mut self) -> Self {
+        self.detect_dialect = true;
+        self
+    }
+
     /// Builds the [`SqlDoc`]
     ///
     ///
@@ -351,113 +804,348 @@ impl<'a> SqlDocBuilder<'a> {
     ///
     /// # Errors
     /// - Will return `DocError` bubbled up
+    /// - Will return [`DocError::UnknownDialect`] if a dialect override or a
+    ///   file's `-- dialect:` header names an unknown dialect
+    /// - Will return [`DocError::MultipleErrors`] listing every failure if
+    ///   [`SqlDocBuilder::collect_all_errors`] was set
     pub fn build<D>(self) -> Result<SqlDoc, DocError>
     where
         D: Dialect + Default,
     {
-        let docs: Vec<SqlFileDoc> = match &self.source {
-            SqlFileDocSource::Dir(path) => generate_docs_from_dir::<_, _, D>(
-                path,
-                &self.deny,
-                self.leading_type,
-                self.multiline_flat,
-            )?,
-            SqlFileDocSource::File(file) => {
-                let sql_doc =
-                    generate_docs_from_file::<_, D>(file, self.leading_type, self.multiline_flat)?;
-                vec![sql_doc]
-            }
-            SqlFileDocSource::FromString(content) => {
-                let sql_docs =
-                    generate_docs_str::<D>(content, None, self.leading_type, self.multiline_flat)?;
-                vec![sql_docs]
-            }
-            SqlFileDocSource::FromStringsWithPaths(strings_paths) => {
-                generate_docs_from_strs_with_paths::<D>(
-                    strings_paths,
-                    self.leading_type,
-                    self.multiline_flat,
-                )?
-            }
-            SqlFileDocSource::Files(files) => {
-                generate_docs_from_files::<D>(files, self.leading_type, self.multiline_flat)?
-            }
+        self.build_with_dialect(&D::default())
+    }
+
+    /// Builds the [`SqlDoc`] with a dialect chosen at runtime rather than as a
+    /// type parameter.
+    ///
+    /// # Parameters
+    /// - `dialect`: the [`Dialect`] used to parse every source
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlDocBuilder::build`]
+    pub fn build_with_dialect(self, dialect: &dyn Dialect) -> Result<SqlDoc, DocError> {
+        self.generate(dialect).map(|(sql_doc, ..)| sql_doc)
+    }
+
+    /// Builds the [`SqlDoc`] with a dialect looked up by name, such as
+    /// `"postgres"` or `"bigquery"` (see [`crate::ast::dialect_from_name`]).
+    ///
+    /// # Parameters
+    /// - `name`: the dialect name, e.g. as read from a configuration file
+    ///
+    /// # Errors
+    /// - Will return [`DocError::UnknownDialect`] if no dialect has that name
+    /// - Will return the same errors as [`SqlDocBuilder::build`]
+    ///
+    /// # Examples
+    /// ```
+    /// use sql_docs::SqlDoc;
+    ///
+    /// let doc = SqlDoc::builder_from_str("CREATE TABLE t (id SERIAL);")
+    ///     .build_with_dialect_name("postgres")
+    ///     .unwrap();
+    /// assert_eq!(doc.table("t", None).unwrap().name(), "t");
+    /// ```
+    pub fn build_with_dialect_name(self, name: &str) -> Result<SqlDoc, DocError> {
+        let dialect = dialect_from_name(name)?;
+        self.build_with_dialect(dialect.as_ref())
+    }
+
+    /// Builds the [`SqlDoc`] and also returns every [`DocWarning`] raised while
+    /// attaching comments, such as orphaned or ambiguous doc comments.
+    ///
+    /// Warnings are grouped by source, in the order the sources were processed.
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlDocBuilder::build`]
+    pub fn build_with_warnings<D>(self) -> Result<(SqlDoc, Vec<DocWarning>), DocError>
+    where
+        D: Dialect + Default,
+    {
+        self.generate(&D::default()).map(|(sql_doc, warnings, _)| (sql_doc, warnings))
+    }
+
+    /// Builds the [`SqlDoc`] and also returns the [`SourceDialect`] each source
+    /// was parsed with, in the order the sources were processed.
+    ///
+    /// The list is empty unless [`SqlDocBuilder::detect_dialect`] was set.
+    ///
+    /// # Errors
+    /// - Will return the same errors as [`SqlDocBuilder::build`]
+    pub fn build_with_source_dialects<D>(self) -> Result<(SqlDoc, Vec<SourceDialect>), DocError>
+    where
+        D: Dialect + Default,
+    {
+        self.generate(&D::default()).map(|(sql_doc, _, source_dialects)| (sql_doc, source_dialects))
+    }
+
+    /// Generates the docs, warnings and reported dialects for every source,
+    /// parsing with `dialect` unless an override or a file header selects
+    /// another one
+    fn generate(
+        self,
+        dialect: &dyn Dialect,
+    ) -> Result<(SqlDoc, Vec<DocWarning>, Vec<SourceDialect>), DocError> {
+        let overrides = self
+            .dialect_overrides
+            .iter()
+            .map(|(pattern, name)| {
+                Ok((GlobPattern::new(pattern), name.clone(), dialect_from_name(name)?))
+            })
+            .collect::<Result<Vec<_>, DocError>>()?;
+        let ctx = &BuildContext {
+            dialects: DialectRules { default: dialect, overrides, detect: self.detect_dialect },
+            capture: self.leading_type,
+            flatten: self.multiline_flat,
+            collect_errors: self.collect_errors,
         };
-        let num_of_tables = docs.iter().map(super::docs::SqlFileDoc::number_of_tables).sum();
+        let mut docs: Vec<FileDocs> = Vec::new();
+        let mut errors = Vec::new();
+        for spec in self.sources {
+            let deny = DenyList::new(&[self.deny.as_slice(), &spec.deny].concat());
+            let encoding = spec.encoding.or(self.encoding);
+            match generate_docs_from_spec(spec.source, &deny, &self.scan, encoding, ctx) {
+                Ok(source_docs) => docs.extend(source_docs),
+                Err(DocError::MultipleErrors { errors: source_errors }) if ctx.collect_errors => {
+                    errors.extend(source_errors);
+                }
+                Err(e) if ctx.collect_errors => errors.push(e),
+                Err(e) => return Err(e),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(DocError::MultipleErrors { errors });
+        }
+        let num_of_tables = docs.iter().map(|(doc, ..)| doc.number_of_tables()).sum();
         let mut tables = Vec::with_capacity(num_of_tables);
-        for sql_doc in docs {
+        let mut warnings = Vec::new();
+        let mut source_dialects = Vec::new();
+        for (sql_doc, file_warnings, source_dialect) in docs {
             tables.extend(sql_doc);
+            warnings.extend(file_warnings);
+            source_dialects.extend(source_dialect);
         }
-        let sql_doc = SqlDoc::new(tables);
-        Ok(sql_doc)
+        Ok((SqlDoc::new(tables), warnings, source_dialects))
     }
 }
 
-fn generate_docs_from_dir<P: AsRef<Path>, S: AsRef<str>, D: Dialect + Default>(
-    source: P,
-    deny: &[S],
+/// The dialects a build can parse each source with
+struct DialectRules<'d> {
+    /// The dialect passed to the build
+    default: &'d dyn Dialect,
+    /// Path globs with the name of their dialect, see [`SqlDocBuilder::dialect_for`]
+    overrides: Vec<(GlobPattern, String, Box<dyn Dialect>)>,
+    /// Whether files without a header or override have their dialect detected
+    detect: bool,
+}
+
+impl DialectRules<'_> {
+    /// Parses `file` with the dialect named in its header, else with the last
+    /// override matching `relative_path` (or the file's own path), else with
+    /// the detected or default dialect. The dialect used is reported when detecting.
+    fn parse(
+        &self,
+        file: SqlSource,
+        relative_path: Option<&Path>,
+    ) -> Result<(ParsedSqlFile, Option<SourceDialect>), DocError> {
+        let path = file.path_into_path_buf();
+        let (parsed, name, detected) = if let Some(name) = file.dialect_directive() {
+            let name = name.to_owned();
+            let dialect = dialect_from_name(&name).map_err(|e| e.with_source(&file))?;
+            (parse_with_context(file, dialect.as_ref())?, name, false)
+        } else if let Some((name, dialect)) = self.override_for(relative_path.or(path.as_deref())) {
+            (parse_with_context(file, dialect)?, name.to_owned(), false)
+        } else if self.detect {
+            let (parsed, name) = ParsedSqlFile::try_parse_detecting_dialect(file)
+                .map_err(|(error, file)| parse_error(error, &file, &GenericDialect {}))?;
+            (parsed, name.to_owned(), true)
+        } else {
+            return Ok((parse_with_context(file, self.default)?, None));
+        };
+        let source_dialect = self.detect.then(|| SourceDialect::new(path, name, detected));
+        Ok((parsed, source_dialect))
+    }
+
+    /// Returns the name and dialect of the last override matching `path`
+    fn override_for(&self, path: Option<&Path>) -> Option<(&str, &dyn Dialect)> {
+        let path = path?;
+        self.overrides
+            .iter()
+            .rev()
+            .find(|(pattern, ..)| pattern.matches(path))
+            .map(|(_, name, dialect)| (name.as_str(), dialect.as_ref()))
+    }
+}
+
+/// Parses `file` with `dialect`, see [`parse_error`] for the error
+fn parse_with_context(file: SqlSource, dialect: &dyn Dialect) -> Result<ParsedSqlFile, DocError> {
+    ParsedSqlFile::try_parse(file, dialect)
+        .map_err(|(error, file)| parse_error(error, &file, dialect))
+}
+
+/// Wraps `error` with the path of `file` and the span parsing it with
+/// `dialect` stopped at
+fn parse_error(error: ParserError, file: &SqlSource, dialect: &dyn Dialect) -> DocError {
+    let span = parse_error_span(file.content(), dialect);
+    DocError::from(error).with_source_span(file, span)
+}
+
+/// The settings shared by every source of one build
+struct BuildContext<'a> {
+    dialects: DialectRules<'a>,
     capture: LeadingCommentCapture,
-    flatten: MultiFlatten,
-) -> Result<Vec<SqlFileDoc>, DocError> {
-    let deny_list: Vec<String> = deny.iter().map(|file| file.as_ref().to_owned()).collect();
-    let file_set = SqlFiles::new(source, &deny_list)?;
-    let mut sql_docs = Vec::new();
-    for file in file_set.sql_files() {
-        let docs = generate_docs_from_file::<_, D>(file, capture, flatten)?;
-        sql_docs.push(docs);
+    flatten: MultiFlatten<'a>,
+    /// Whether to keep going after a source fails, see [`generate_all`]
+    collect_errors: bool,
+}
+
+/// Generates the docs for one source of the build, skipping denied paths and
+/// decoding files with `encoding`, or the detected one when `None`
+fn generate_docs_from_spec(
+    source: SqlFileDocSource<'_>,
+    deny: &DenyList,
+    scan: &ScanOptions,
+    encoding: Option<Encoding>,
+    ctx: &BuildContext<'_>,
+) -> Result<Vec<FileDocs>, DocError> {
+    match source {
+        SqlFileDocSource::Dir(path) => {
+            generate_docs_from_provider(&FsProvider::new(path), deny, scan, encoding, ctx)
+        }
+        SqlFileDocSource::Provider(provider) => {
+            generate_docs_from_provider(provider, deny, scan, encoding, ctx)
+        }
+        SqlFileDocSource::File(file) => {
+            generate_docs_from_files(slice::from_ref(&file), deny, encoding, ctx)
+        }
+        SqlFileDocSource::Files(files) => generate_docs_from_files(&files, deny, encoding, ctx),
+        SqlFileDocSource::FromString(content) => Ok(vec![generate_docs_str(content, None, ctx)?]),
+        SqlFileDocSource::FromStringsWithPaths(strings_paths) => {
+            generate_docs_from_strs_with_paths(strings_paths, deny, ctx)
+        }
+        SqlFileDocSource::Reader(ReaderSource { path: Some(path), .. })
+            if deny.deny_files().contains(&path) =>
+        {
+            Ok(Vec::new())
+        }
+        SqlFileDocSource::Reader(ReaderSource { reader, path }) => {
+            let source = SqlSource::from_reader(reader, path.clone(), encoding).map_err(|e| {
+                let e = DocError::from(e);
+                match path.as_deref() {
+                    Some(path) => e.with_path(path),
+                    None => e,
+                }
+            })?;
+            Ok(vec![generate_docs_from_source(source, None, ctx)?])
+        }
     }
-    Ok(sql_docs)
 }
 
-fn generate_docs_from_files<D: Dialect + Default>(
+/// Generates the docs for every file `provider` lists, matching patterns and
+/// dialect overrides against paths relative to the provider's root
+fn generate_docs_from_provider(
+    provider: &dyn SourceProvider,
+    deny: &DenyList,
+    scan: &ScanOptions,
+    encoding: Option<Encoding>,
+    ctx: &BuildContext<'_>,
+) -> Result<Vec<FileDocs>, DocError> {
+    let files = provider.list(scan).map_err(|e| {
+        let e = DocError::from(e);
+        match provider.root() {
+            Some(root) => e.with_path(root),
+            None => e,
+        }
+    })?;
+    let files = files.into_iter().filter(|file| !deny.deny_files().contains(file));
+    generate_all(files, ctx, |file| {
+        let relative = provider.root().and_then(|root| file.strip_prefix(root).ok());
+        let source = SqlSource::from_provider(provider, &file, encoding)
+            .map_err(|e| DocError::from(e).with_path(&file))?;
+        generate_docs_from_source(source, relative, ctx)
+    })
+}
+
+fn generate_docs_from_files(
     files: &[PathBuf],
-    capture: LeadingCommentCapture,
-    flatten: MultiFlatten,
-) -> Result<Vec<SqlFileDoc>, DocError> {
+    deny: &DenyList,
+    encoding: Option<Encoding>,
+    ctx: &BuildContext<'_>,
+) -> Result<Vec<FileDocs>, DocError> {
+    let files = files.iter().filter(|file| !deny.deny_files().contains(file));
+    generate_all(files, ctx, |file| generate_docs_from_file(file, encoding, ctx))
+}
+
+/// Generates the docs for every input, either stopping at the first error or,
+/// when `collect_errors` is set, processing every input and returning all
+/// failures as [`DocError::MultipleErrors`].
+fn generate_all<I: IntoIterator>(
+    inputs: I,
+    ctx: &BuildContext<'_>,
+    mut generate: impl FnMut(I::Item) -> Result<FileDocs, DocError>,
+) -> Result<Vec<FileDocs>, DocError> {
     let mut sql_docs = Vec::new();
-    for file in files {
-        let docs = generate_docs_from_file::<_, D>(file, capture, flatten)?;
-        sql_docs.push(docs);
+    let mut errors = Vec::new();
+    for input in inputs {
+        match generate(input) {
+            Ok(docs) => sql_docs.push(docs),
+            Err(e) if ctx.collect_errors => errors.push(e),
+            Err(e) => return Err(e),
+        }
     }
-    Ok(sql_docs)
+    if errors.is_empty() { Ok(sql_docs) } else { Err(DocError::MultipleErrors { errors }) }
 }
 
-fn generate_docs_from_file<P: AsRef<Path>, D: Dialect + Default>(
+fn generate_docs_from_file<P: AsRef<Path>>(
     source: P,
-    capture: LeadingCommentCapture,
-    flatten: MultiFlatten,
-) -> Result<SqlFileDoc, DocError> {
-    let file = SqlSource::from_path(source.as_ref())?;
-    let parsed_file = ParsedSqlFile::parse::<D>(file)?;
-    let comments = Comments::parse_all_comments_from_file(&parsed_file)?;
-    let docs = SqlFileDoc::from_parsed_file(&parsed_file, &comments, capture, flatten)?;
-    Ok(docs)
+    encoding: Option<Encoding>,
+    ctx: &BuildContext<'_>,
+) -> Result<FileDocs, DocError> {
+    let file = SqlSource::from_path_with_encoding(source.as_ref(), encoding)
+        .map_err(|e| DocError::from(e).with_path(source.as_ref()))?;
+    generate_docs_from_source(file, None, ctx)
+}
+
+fn generate_docs_from_source(
+    file: SqlSource,
+    relative_path: Option<&Path>,
+    ctx: &BuildContext<'_>,
+) -> Result<FileDocs, DocError> {
+    let (parsed_file, source_dialect) = ctx.dialects.parse(file, relative_path)?;
+    let mut comments = Comments::parse_all_comments_from_file(&parsed_file)
+        .map_err(|e| DocError::from(e).with_source(parsed_file.file()))?;
+    if let Some((line, _)) = parsed_file.file().header_dialect_directive() {
+        comments = comments.without_dialect_directive(line);
+    }
+    let (docs, warnings) = SqlFileDoc::from_parsed_file_with_warnings(
+        &parsed_file,
+        &comments,
+        ctx.capture,
+        ctx.flatten,
+    )
+    .map_err(|e| e.with_source(parsed_file.file()))?;
+    Ok((docs, warnings, source_dialect))
 }
 
-fn generate_docs_str<D: Dialect + Default>(
+fn generate_docs_str(
     content: &str,
     path: Option<PathBuf>,
-    capture: LeadingCommentCapture,
-    flatten: MultiFlatten,
-) -> Result<SqlFileDoc, DocError> {
+    ctx: &BuildContext<'_>,
+) -> Result<FileDocs, DocError> {
     let dummy_file = SqlSource::from_str(content.to_owned(), path);
-    let parsed_sql = ParsedSqlFile::parse::<D>(dummy_file)?;
-    let comments = Comments::parse_all_comments_from_file(&parsed_sql)?;
-    let docs = SqlFileDoc::from_parsed_file(&parsed_sql, &comments, capture, flatten)?;
-    Ok(docs)
+    generate_docs_from_source(dummy_file, None, ctx)
 }
 
-fn generate_docs_from_strs_with_paths<D: Dialect + Default>(
+fn generate_docs_from_strs_with_paths(
     strings_with_paths: &[(String, PathBuf)],
-    capture: LeadingCommentCapture,
-    flatten: MultiFlatten,
-) -> Result<Vec<SqlFileDoc>, DocError> {
-    let mut docs = Vec::new();
-    for (content, path) in strings_with_paths {
-        docs.push(generate_docs_str::<D>(content, Some(path.to_owned()), capture, flatten)?);
-    }
-
-    Ok(docs)
+    deny: &DenyList,
+    ctx: &BuildContext<'_>,
+) -> Result<Vec<FileDocs>, DocError> {
+    let strings_with_paths =
+        strings_with_paths.iter().filter(|(_, path)| !deny.deny_files().contains(path));
+    generate_all(strings_with_paths, ctx, |(content, path)| {
+        generate_docs_str(content, Some(path.to_owned()), ctx)
+    })
 }
 
 #[cfg(test)]
@@ -468,14 +1156,17 @@ mod tests {
         vec,
     };
 
-    use sqlparser::dialect::{GenericDialect, PostgreSqlDialect};
+    use sqlparser::dialect::{AnsiDialect, GenericDialect, PostgreSqlDialect};
 
     use crate::{
-        SqlDoc,
+        DocWarning, SqlDoc,
         comments::LeadingCommentCapture,
         docs::{ColumnDoc, TableDoc},
+        encoding::Encoding,
         error::DocError,
-        sql_doc::{MultiFlatten, SqlDocBuilder},
+        files::ScanOptions,
+        provider::MemoryProvider,
+        sql_doc::{MultiFlatten, SourceSpec, SqlDocBuilder, SqlFileDocSource},
     };
 
     #[test]
@@ -591,6 +1282,19 @@ mod tests {
         let pb = path.to_path_buf();
         for t in tables {
             t.set_path(Some(pb.clone()));
+            for column in t.columns_mut() {
+                *column = with_definition(column.clone());
+            }
+        }
+    }
+
+    /// Declares a column of [`sample_sql`] the way its `CREATE TABLE` does
+    fn with_definition(column: ColumnDoc) -> ColumnDoc {
+        match column.name() {
+            "id" => column.with_data_type("INTEGER").with_primary_key(true),
+            "username" | "title" => column.with_data_type("TEXT").with_nullable(false),
+            "value" => column.with_data_type("INTEGER"),
+            _ => column.with_data_type("TEXT"),
         }
     }
 
@@ -693,10 +1397,19 @@ mod tests {
     fn test_sql_builder_deny_from_path() {
         let actual_builder = SqlDoc::from_path("path").deny("path1").deny("path2");
         let expected_builder = SqlDocBuilder {
-            source: crate::sql_doc::SqlFileDocSource::File(PathBuf::from("path")),
-            deny: vec!["path1".to_owned(), "path2".to_owned()],
+            sources: vec![SourceSpec {
+                source: SqlFileDocSource::File(PathBuf::from("path")),
+                deny: vec!["path1".to_owned(), "path2".to_owned()],
+                encoding: None,
+            }],
+            deny: vec![],
+            scan: ScanOptions::new(),
             multiline_flat: MultiFlatten::default(),
             leading_type: LeadingCommentCapture::default(),
+            collect_errors: false,
+            dialect_overrides: Vec::new(),
+            detect_dialect: false,
+            encoding: None,
         };
         assert_eq!(actual_builder, expected_builder);
     }
@@ -850,10 +1563,19 @@ mod tests {
         let actual = SqlDoc::builder_from_str(content);
 
         let expected = SqlDocBuilder {
-            source: crate::sql_doc::SqlFileDocSource::FromString(content),
+            sources: vec![SourceSpec {
+                source: SqlFileDocSource::FromString(content),
+                deny: vec![],
+                encoding: None,
+            }],
             deny: vec![],
+            scan: ScanOptions::new(),
             multiline_flat: MultiFlatten::default(),
             leading_type: LeadingCommentCapture::default(),
+            collect_errors: false,
+            dialect_overrides: Vec::new(),
+            detect_dialect: false,
+            encoding: None,
         };
 
         assert_eq!(actual, expected);
@@ -1096,4 +1818,391 @@ mod tests {
         assert_eq!(doc.table("t", None)?.name(), "t");
         Ok(())
     }
+
+    #[test]
+    fn test_build_errors_carry_file_path_and_location() -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("build_errors_carry_file_path");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        let good = base.join("good.sql");
+        let bad = base.join("bad.sql");
+        fs::write(&good, "CREATE TABLE ok (id INTEGER);")?;
+        fs::write(&bad, "CREATE TABLE ok (id INTEGER);\nCREATE TABLE broken (id INTEGER,,);")?;
+
+        let Err(err) = SqlDoc::from_dir(&base).build::<GenericDialect>() else {
+            panic!("expected build to fail on bad.sql");
+        };
+        assert_eq!(err.path(), Some(bad.as_path()));
+        assert_eq!(err.location(), Some(crate::comments::Location::new(2, 32)));
+        assert!(
+            err.to_string().ends_with(
+                "2 | CREATE TABLE broken (id INTEGER,,);\n  |                                ^^"
+            ),
+            "output was: {err}"
+        );
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_all_errors_reports_every_failing_file() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let base = env::temp_dir().join("collect_all_errors_reports_every_failing_file");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        let bad1 = base.join("a_bad.sql");
+        let good = base.join("b_good.sql");
+        let bad2 = base.join("c_bad.sql");
+        fs::write(&bad1, "CREATE TABLE broken (id INTEGER,,);")?;
+        fs::write(&good, "CREATE TABLE ok (id INTEGER);")?;
+        fs::write(&bad2, "/* unterminated\nCREATE TABLE t (id INTEGER);")?;
+
+        let Err(first_only) = SqlDoc::from_dir(&base).build::<GenericDialect>() else {
+            panic!("expected build to fail");
+        };
+        assert_eq!(first_only.path(), Some(bad1.as_path()));
+
+        let Err(DocError::MultipleErrors { errors }) =
+            SqlDoc::from_dir(&base).collect_all_errors().build::<GenericDialect>()
+        else {
+            panic!("expected DocError::MultipleErrors");
+        };
+        let paths: Vec<_> = errors.iter().map(DocError::path).collect();
+        assert_eq!(paths, vec![Some(bad1.as_path()), Some(bad2.as_path())]);
+
+        let ok = SqlDoc::from_path(&good).collect_all_errors().build::<GenericDialect>()?;
+        assert_eq!(ok.number_of_tables(), 1);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_with_warnings_reports_orphaned_comments() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let sql = "\
+-- Users table
+CREATE TABLE users (
+    -- id
+    id INTEGER
+);
+
+-- Orphaned by the blank line below
+
+CREATE TABLE posts (id INTEGER);
+";
+        let (doc, warnings) =
+            SqlDoc::builder_from_str(sql).build_with_warnings::<GenericDialect>()?;
+        assert_eq!(doc, SqlDoc::builder_from_str(sql).build::<GenericDialect>()?);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(&warnings[0], DocWarning::OrphanedComment { path: None, comment }
+            if comment.text() == "Orphaned by the blank line below"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_with_runtime_dialect() -> Result<(), Box<dyn std::error::Error>> {
+        let sql = "-- Users\nCREATE TABLE users (id INTEGER PRIMARY KEY);";
+        let expected = SqlDoc::builder_from_str(sql).build::<PostgreSqlDialect>()?;
+
+        let by_name = SqlDoc::builder_from_str(sql).build_with_dialect_name("postgres")?;
+        assert_eq!(by_name, expected);
+
+        let by_ref = SqlDoc::builder_from_str(sql).build_with_dialect(&PostgreSqlDialect {})?;
+        assert_eq!(by_ref, expected);
+
+        match SqlDoc::builder_from_str(sql).build_with_dialect_name("cobol") {
+            Err(DocError::UnknownDialect { name }) => assert_eq!(name, "cobol"),
+            other => panic!("expected UnknownDialect, got: {other:?}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_dialect_overrides_and_headers_build_mixed_sources()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("dialect_overrides_mixed_sources");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("app"))?;
+        fs::create_dir_all(base.join("warehouse"))?;
+        fs::write(base.join("app/users.sql"), "CREATE TABLE users (id INTEGER);")?;
+        fs::write(
+            base.join("warehouse/events.sql"),
+            "CREATE TABLE events (id INT64) OPTIONS(description = 'x');",
+        )?;
+        fs::write(
+            base.join("app/payloads.sql"),
+            "-- dialect: bigquery\nCREATE TABLE payloads (\n  -- dialect: the sending client\n  \
+             body STRUCT<a INT64>\n);",
+        )?;
+
+        let plain = SqlDoc::from_dir(&base).build_with_dialect(&AnsiDialect {});
+        assert!(plain.is_err());
+
+        let doc = SqlDoc::from_dir(&base)
+            .dialect_for("warehouse/**", "bigquery")
+            .build_with_dialect(&AnsiDialect {})?;
+        assert_eq!(doc.number_of_tables(), 3);
+        let payloads = doc.table("payloads", None)?;
+        assert_eq!(payloads.doc(), None);
+        assert_eq!(payloads.column("body")?.doc(), Some("dialect: the sending client"));
+
+        let unknown = SqlDoc::from_dir(&base)
+            .dialect_for("warehouse/**", "nosuchdialect")
+            .build_with_dialect(&AnsiDialect {});
+        assert!(
+            matches!(unknown, Err(DocError::UnknownDialect { name }) if name == "nosuchdialect")
+        );
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_dialect_reports_dialect_per_source() -> Result<(), Box<dyn std::error::Error>> {
+        let inputs = vec![
+            ("CREATE TABLE a (id INTEGER);".to_owned(), PathBuf::from("a.sql")),
+            ("CREATE TABLE `b` (id INT AUTO_INCREMENT);".to_owned(), PathBuf::from("b.sql")),
+            (
+                "-- dialect: postgres\nCREATE TABLE c (id SERIAL);".to_owned(),
+                PathBuf::from("c.sql"),
+            ),
+            (
+                "CREATE TABLE d (id INT64) OPTIONS(description = 'x');".to_owned(),
+                PathBuf::from("d.sql"),
+            ),
+        ];
+        let (doc, source_dialects) = SqlDoc::builder_from_strs_with_paths(&inputs)
+            .detect_dialect()
+            .build_with_source_dialects::<AnsiDialect>()?;
+        assert_eq!(doc.number_of_tables(), 4);
+        let reported: Vec<(Option<&Path>, &str, bool)> = source_dialects
+            .iter()
+            .map(|source| (source.path(), source.dialect(), source.detected()))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                (Some(Path::new("a.sql")), "generic", true),
+                (Some(Path::new("b.sql")), "mysql", true),
+                (Some(Path::new("c.sql")), "postgres", false),
+                (Some(Path::new("d.sql")), "bigquery", true),
+            ]
+        );
+
+        let (undetected, source_dialects) = SqlDoc::builder_from_strs_with_paths(&inputs[..1])
+            .build_with_source_dialects::<GenericDialect>()?;
+        assert!(source_dialects.is_empty());
+        let detected = SqlDoc::builder_from_strs_with_paths(&inputs[..1])
+            .detect_dialect()
+            .build::<GenericDialect>()?;
+        assert_eq!(detected, undetected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_and_exclude_patterns_filter_dir_scan() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let base = env::temp_dir().join("builder_include_exclude_patterns");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("seed"))?;
+        fs::create_dir_all(base.join("archive/seed"))?;
+        fs::write(base.join("seed/users.sql"), "CREATE TABLE users (id INTEGER);")?;
+        fs::write(base.join("archive/seed/old.sql"), "CREATE TABLE old (id INTEGER);")?;
+        fs::write(base.join("schema.sql"), "CREATE TABLE schema_only (id INTEGER);")?;
+
+        let doc = SqlDoc::from_dir(&base)
+            .include("**/seed/*.sql")
+            .include("!**/archive/**")
+            .build::<GenericDialect>()?;
+        assert_eq!(doc.number_of_tables(), 1);
+        assert!(doc.table("users", None).is_ok());
+
+        let doc = SqlDoc::from_dir(&base).exclude("archive/**").build::<GenericDialect>()?;
+        assert_eq!(doc.number_of_tables(), 2);
+        assert!(doc.table("old", None).is_err());
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_decodes_bom_utf16_and_explicit_latin1() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let base = env::temp_dir().join("build_decodes_source_encodings");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        let utf16 = base.join("ssms.sql");
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "-- Exported users\nCREATE TABLE users (id INT);".encode_utf16() {
+            bytes.extend(unit.to_le_bytes());
+        }
+        fs::write(&utf16, bytes)?;
+        let latin1 = base.join("oracle.sql");
+        fs::write(&latin1, b"-- Caf\xe9 orders\nCREATE TABLE orders (id INT);")?;
+
+        let doc = SqlDoc::from_path(&utf16).build::<GenericDialect>()?;
+        let users = doc.table("users", None)?;
+        assert_eq!(users.doc(), Some("Exported users"));
+
+        let err = SqlDoc::from_path(&latin1).build::<GenericDialect>();
+        assert!(matches!(err, Err(ref e) if e.path() == Some(latin1.as_path())));
+
+        let doc =
+            SqlDoc::from_path(&latin1).encoding(Encoding::Latin1).build::<GenericDialect>()?;
+        assert_eq!(doc.table("orders", None)?.doc(), Some("Café orders"));
+
+        let utf8 = base.join("app.sql");
+        fs::write(&utf8, "-- Crème brûlée\nCREATE TABLE desserts (id INT);")?;
+        let doc = SqlDoc::from_path(&latin1)
+            .encoding(Encoding::Latin1)
+            .add_path(&utf8)
+            .add_path(&utf16)
+            .build::<GenericDialect>()?;
+        assert_eq!(doc.table("orders", None)?.doc(), Some("Café orders"));
+        assert_eq!(doc.table("desserts", None)?.doc(), Some("Crème brûlée"));
+        assert_eq!(doc.table("users", None)?.doc(), Some("Exported users"));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_from_memory_provider() -> Result<(), Box<dyn std::error::Error>> {
+        let files = MemoryProvider::new()
+            .with_file("app/users.sql", "-- Accounts\nCREATE TABLE users (id INTEGER);")
+            .with_file("warehouse/events.sql", "CREATE TABLE events (payload STRUCT<a INT64>);")
+            .with_file("app/broken.sql", "CREATE TABLE broken (")
+            .with_file("notes.md", "# not sql");
+        let doc = SqlDoc::from_provider(&files)
+            .dialect_for("warehouse/**", "bigquery")
+            .deny("app/broken.sql")
+            .build::<AnsiDialect>()?;
+        assert_eq!(doc.number_of_tables(), 2);
+        let users = doc.table("users", None)?;
+        assert_eq!(users.doc(), Some("Accounts"));
+        assert_eq!(users.path(), Some(Path::new("app/users.sql")));
+
+        let err = SqlDoc::from_provider(&files).build::<GenericDialect>();
+        assert!(matches!(err, Err(ref e) if e.path() == Some(Path::new("app/broken.sql"))));
+        assert_eq!(SqlDoc::from_provider(&files), SqlDoc::from_provider(&files));
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_combines_sources_with_their_own_deny_rules()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let base = env::temp_dir().join("builder_combines_sources");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("schema"))?;
+        fs::create_dir_all(base.join("extra"))?;
+        fs::write(base.join("schema/users.sql"), "CREATE TABLE users (id INTEGER);")?;
+        fs::write(base.join("schema/legacy.sql"), "CREATE TABLE legacy (")?;
+        fs::write(base.join("extra/views.sql"), "CREATE TABLE views (id INTEGER);")?;
+        fs::write(base.join("extra/roles.sql"), "CREATE TABLE roles (id INTEGER);")?;
+        let generated = "CREATE TABLE audit_log (id INTEGER);".to_owned();
+        let files =
+            MemoryProvider::new().with_file("mem/cache.sql", "CREATE TABLE cache (id INT);");
+
+        let doc = SqlDoc::builder()
+            .add_dir(&base.join("schema"))
+            .deny(&base.join("schema/legacy.sql").to_string_lossy())
+            .add_paths(&[base.join("extra/views.sql"), base.join("extra/roles.sql")])
+            .deny(&base.join("extra/roles.sql").to_string_lossy())
+            .add_str(&generated)
+            .add_provider(&files)
+            .build::<GenericDialect>()?;
+        let names: Vec<&str> = doc.tables().iter().map(TableDoc::name).collect();
+        assert_eq!(names, vec!["audit_log", "cache", "users", "views"]);
+        assert_eq!(doc.table("audit_log", None)?.path(), None);
+
+        let err = SqlDoc::builder()
+            .add_dir(&base.join("schema"))
+            .add_str("CREATE TABLE broken (")
+            .collect_all_errors()
+            .build::<GenericDialect>();
+        assert!(matches!(err, Err(DocError::MultipleErrors { ref errors }) if errors.len() == 2));
+        assert_eq!(SqlDoc::builder().build::<GenericDialect>()?.number_of_tables(), 0);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_from_reader_with_virtual_path() -> Result<(), Box<dyn std::error::Error>> {
+        let dump: &[u8] = b"-- Accounts\nCREATE TABLE users (id INTEGER);";
+        let doc = SqlDoc::from_reader(dump, None).build::<GenericDialect>()?;
+        assert_eq!(doc.table("users", None)?.path(), None);
+
+        let doc = SqlDoc::builder_from_str("CREATE TABLE posts (id INTEGER);")
+            .add_reader(dump, Some(Path::new("dump.sql")))
+            .build::<GenericDialect>()?;
+        assert_eq!(doc.number_of_tables(), 2);
+        assert_eq!(doc.table("users", None)?.path(), Some(Path::new("dump.sql")));
+
+        let broken: &[u8] = b"CREATE TABLE broken (";
+        let err =
+            SqlDoc::from_reader(broken, Some(Path::new("stdin.sql"))).build::<GenericDialect>();
+        assert!(matches!(err, Err(ref e) if e.path() == Some(Path::new("stdin.sql"))));
+        let denied = SqlDoc::from_reader(broken, Some(Path::new("stdin.sql")))
+            .deny("stdin.sql")
+            .build::<GenericDialect>()?;
+        assert_eq!(denied.number_of_tables(), 0);
+
+        let invalid: &[u8] = b"-- Caf\xe9\nCREATE TABLE t (id INTEGER);";
+        let err =
+            SqlDoc::from_reader(invalid, Some(Path::new("latin1.sql"))).build::<GenericDialect>();
+        assert!(matches!(err, Err(ref e) if e.path() == Some(Path::new("latin1.sql"))));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_sql_doc_serde_round_trip_is_versioned() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::sql_doc::SERDE_FORMAT_VERSION;
+
+        let doc = SqlDoc::builder_from_str(
+            "-- Accounts\nCREATE TABLE app.users (\n  -- Login\n  name TEXT,\n  id INTEGER\n);",
+        )
+        .build::<GenericDialect>()?;
+        let json = serde_json::to_value(&doc)?;
+        assert_eq!(json["format_version"], SERDE_FORMAT_VERSION);
+        assert_eq!(json["tables"][0]["schema"], "app");
+        assert_eq!(json["tables"][0]["columns"][1]["doc"], "Login");
+        let round_trip = serde_json::from_value::<SqlDoc>(json.clone())?;
+        assert_eq!(round_trip, doc);
+
+        let mut unsorted = json.clone();
+        if let Some(columns) = unsorted["tables"][0]["columns"].as_array_mut() {
+            columns.reverse();
+        }
+        let restored: SqlDoc = serde_json::from_value(unsorted)?;
+        assert_eq!(restored.table("users", Some("app"))?.column("id")?.name(), "id");
+
+        let mut newer = json;
+        newer["format_version"] = (SERDE_FORMAT_VERSION + 1).into();
+        let err = serde_json::from_value::<SqlDoc>(newer).err();
+        assert!(err.is_some_and(|e| e.to_string().contains("is newer than the supported version")));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_comment_serializes_kind_and_span() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::comments::{Comment, CommentKind, Location, Span};
+
+        let comment = Comment::new(
+            "Login".to_owned(),
+            CommentKind::SingleLine,
+            Span::new(Location::new(3, 3), Location::new(3, 11)),
+        );
+        let json = serde_json::to_string(&comment)?;
+        assert_eq!(
+            json,
+            r#"{"text":"Login","kind":"single_line","span":{"start":{"line":3,"column":3},"end":{"line":3,"column":11}}}"#
+        );
+        assert_eq!(serde_json::from_str::<Comment>(&json)?, comment);
+        Ok(())
+    }
 }
@@ -0,0 +1,137 @@
+//! Non-fatal diagnostics reported alongside a built [`crate::SqlDoc`].
+//!
+//! Warnings never stop a build; they point at comments that were probably meant
+//! as documentation but did not end up where the author expected.
+
+use core::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::comments::Comment;
+
+/// A non-fatal issue found while attaching comments to tables and columns.
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DocWarning {
+    /// A comment on its own line(s) that is not attached to any table or column,
+    /// e.g. because it is followed by a blank line or precedes a `DROP` statement
+    OrphanedComment {
+        /// The path of the source, `None` if it was built from a string
+        path: Option<PathBuf>,
+        /// The comment that was not attached
+        comment: Comment,
+    },
+    /// A comment that was attached to more than one table or column because
+    /// they start on the same line
+    AmbiguousComment {
+        /// The path of the source, `None` if it was built from a string
+        path: Option<PathBuf>,
+        /// The comment that was attached more than once
+        comment: Comment,
+        /// The qualified names of every object the comment was attached to
+        targets: Vec<String>,
+    },
+    /// A column whose comment was found before the start of the statement that
+    /// defines it, so it most likely documents something else
+    UnrelatedColumnComment {
+        /// The path of the source, `None` if it was built from a string
+        path: Option<PathBuf>,
+        /// The qualified name of the table the column belongs to
+        table: String,
+        /// The name of the column
+        column: String,
+        /// The comment that was attached to the column
+        comment: Comment,
+    },
+}
+
+impl DocWarning {
+    /// Returns the path of the source the warning was raised for, if known
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::OrphanedComment { path, .. }
+            | Self::AmbiguousComment { path, .. }
+            | Self::UnrelatedColumnComment { path, .. } => path.as_deref(),
+        }
+    }
+
+    /// Returns the [`Comment`] the warning is about
+    #[must_use]
+    pub const fn comment(&self) -> &Comment {
+        match self {
+            Self::OrphanedComment { comment, .. }
+            | Self::AmbiguousComment { comment, .. }
+            | Self::UnrelatedColumnComment { comment, .. } => comment,
+        }
+    }
+}
+
+impl fmt::Display for DocWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path().map_or_else(|| "<string>".into(), Path::to_string_lossy);
+        let start = self.comment().span().start();
+        write!(f, "{path}:{}:{}: ", start.line(), start.column())?;
+        match self {
+            Self::OrphanedComment { .. } => {
+                write!(f, "comment is not attached to any table or column")
+            }
+            Self::AmbiguousComment { targets, .. } => {
+                write!(f, "comment is attached to multiple objects: {}", targets.join(", "))
+            }
+            Self::UnrelatedColumnComment { table, column, .. } => {
+                write!(f, "comment for column {table}.{column} precedes the statement defining it")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::{
+        comments::{Comment, CommentKind, Location, Span},
+        warnings::DocWarning,
+    };
+
+    fn comment_at(line: u64, column: u64) -> Comment {
+        Comment::new(
+            "doc".to_owned(),
+            CommentKind::SingleLine,
+            Span::new(Location::new(line, column), Location::new(line, column + 6)),
+        )
+    }
+
+    #[test]
+    fn test_doc_warning_display() {
+        let orphan = DocWarning::OrphanedComment {
+            path: Some(PathBuf::from("a.sql")),
+            comment: comment_at(3, 1),
+        };
+        assert_eq!(orphan.to_string(), "a.sql:3:1: comment is not attached to any table or column");
+        assert_eq!(orphan.path(), Some(Path::new("a.sql")));
+
+        let ambiguous = DocWarning::AmbiguousComment {
+            path: None,
+            comment: comment_at(2, 5),
+            targets: vec!["t.a".to_owned(), "t.b".to_owned()],
+        };
+        assert_eq!(
+            ambiguous.to_string(),
+            "<string>:2:5: comment is attached to multiple objects: t.a, t.b"
+        );
+
+        let unrelated = DocWarning::UnrelatedColumnComment {
+            path: None,
+            table: "users".to_owned(),
+            column: "id".to_owned(),
+            comment: comment_at(1, 1),
+        };
+        assert_eq!(
+            unrelated.to_string(),
+            "<string>:1:1: comment for column users.id precedes the statement defining it"
+        );
+        assert_eq!(unrelated.comment(), &comment_at(1, 1));
+        assert_eq!(unrelated.path(), None);
+    }
+}